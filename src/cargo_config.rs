@@ -0,0 +1,242 @@
+use crate::error::{io_error, TrimError};
+use std::{fs, path::Path};
+
+// index url of crates.io which is always known to cargo without config
+const CRATES_IO_INDEX: &str = "https://github.com/rust-lang/crates.io-index";
+const CRATES_IO_SPARSE_INDEX: &str = "sparse+https://index.crates.io/";
+
+/// Registries known to cargo, read from cargo config file present in cargo home
+/// along with crates.io
+pub struct CargoConfig {
+    registries: Vec<(String, String)>,
+}
+
+impl CargoConfig {
+    /// create new `CargoConfig` which only knows about crates.io
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            registries: vec![
+                ("crates-io".to_string(), CRATES_IO_INDEX.to_string()),
+                ("crates-io".to_string(), CRATES_IO_SPARSE_INDEX.to_string()),
+            ],
+        }
+    }
+
+    /// read `registries` and `source` table of `config.toml` and `config` file
+    /// present in cargo home, file which doesn't exist is skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if cargo config file cannot be read or parsed
+    pub fn load(cargo_home: &Path) -> Result<Self, TrimError> {
+        let mut cargo_config = Self::new();
+        for file_name in &["config.toml", "config"] {
+            let path = cargo_home.join(file_name);
+            if !path.is_file() {
+                continue;
+            }
+            let buffer =
+                fs::read_to_string(&path).map_err(io_error(&path, "read cargo config file"))?;
+            let value =
+                toml::from_str::<toml::Value>(&buffer).map_err(|source| TrimError::Toml {
+                    path: path.clone(),
+                    source,
+                })?;
+            for (table, key) in &[("registries", "index"), ("source", "registry")] {
+                if let Some(entries) = value.get(table).and_then(toml::Value::as_table) {
+                    for (name, entry) in entries {
+                        if let Some(url) = entry.get(key).and_then(toml::Value::as_str) {
+                            cargo_config.add_registry(name, url);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(cargo_config)
+    }
+
+    /// add registry with its index url
+    pub fn add_registry(&mut self, name: &str, url: &str) {
+        self.registries.push((name.to_string(), url.to_string()));
+    }
+
+    /// return index url of registry directory present inside registry src, cache
+    /// or index directory. Directory name is made up of host and hash of index
+    /// url so registry whose hash matches is used and first registry with same
+    /// host is used when hash is not known
+    #[must_use]
+    pub fn url(&self, registry_dir: &str) -> Option<&str> {
+        let host = registry_host(registry_dir);
+        self.registries
+            .iter()
+            .find(|(_, url)| {
+                registry_dir_names(url)
+                    .iter()
+                    .any(|name| name == registry_dir)
+            })
+            .or_else(|| {
+                self.registries
+                    .iter()
+                    .find(|(_, url)| url_host(url) == Some(host))
+            })
+            .map(|(_, url)| url.as_str())
+    }
+
+    /// check if registry directory matches provided registry name, index url or
+    /// directory name. Directory name is made up of host and hash of index url
+    /// so index url is compared by hash, host is only compared when directory
+    /// hash doesn't match any known index url
+    #[must_use]
+    pub fn matches(&self, registry_dir: &str, filter: &str) -> bool {
+        if registry_dir == filter {
+            return true;
+        }
+        let urls = if filter.starts_with("registry+") || filter.starts_with("sparse+") {
+            vec![filter.to_string()]
+        } else if url_host(filter).is_some() {
+            // index url passed without prefix can be either git or sparse index
+            vec![filter.to_string(), format!("sparse+{filter}")]
+        } else {
+            self.registries
+                .iter()
+                .filter(|(name, _)| name == filter)
+                .map(|(_, url)| url.clone())
+                .collect()
+        };
+        let hashes_to = |url: &String| {
+            registry_dir_names(url)
+                .iter()
+                .any(|name| name == registry_dir)
+        };
+        if urls.iter().any(hashes_to) {
+            return true;
+        }
+        let known = self.registries.iter().any(|(_, url)| hashes_to(url));
+        let host = registry_host(registry_dir);
+        !known && urls.iter().any(|url| url_host(url) == Some(host))
+    }
+}
+
+impl Default for CargoConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// get host from registry directory name which is in format of host-hash
+pub(crate) fn registry_host(registry_dir: &str) -> &str {
+    registry_dir
+        .rsplit_once('-')
+        .map_or(registry_dir, |(host, _)| host)
+}
+
+// get host from index url, url can be prefixed with registry+ or sparse+ like
+// source of Cargo.lock package
+pub(crate) fn url_host(url: &str) -> Option<&str> {
+    let url = url
+        .trim_start_matches("registry+")
+        .trim_start_matches("sparse+");
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split('/').next()?;
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, authority)| authority);
+    authority.split(':').next()
+}
+
+// list out name of registry directory used by cargo for index url, url can be
+// prefixed with registry+ or sparse+ like source of Cargo.lock package. Name is
+// host followed by hash of source kind and url, cargo before 1.85 used SipHash
+// 2-4 while later version uses stable SipHash 1-3 so both names are listed
+pub(crate) fn registry_dir_names(url: &str) -> Vec<String> {
+    let Some(host) = url_host(url) else {
+        return Vec::new();
+    };
+    // discriminant of registry and sparse registry variant of cargo source kind
+    let (kind, url) = match url.strip_prefix("registry+") {
+        Some(url) => (2_u8, url.to_string()),
+        None if url.starts_with("sparse+") => (3, url.to_string()),
+        None => (2, url.to_string()),
+    };
+    // url without path is normalized with trailing slash
+    let url = match url.split_once("://") {
+        Some((_, rest)) if !rest.contains('/') => format!("{url}/"),
+        _ => url,
+    };
+    let mut legacy = Vec::new();
+    legacy.extend_from_slice(&u64::from(kind).to_le_bytes());
+    legacy.extend_from_slice(url.as_bytes());
+    legacy.push(0xff);
+    let mut stable = vec![kind];
+    stable.extend_from_slice(url.as_bytes());
+    stable.push(0xff);
+    let (legacy_hash, _) = sip_hash(&legacy, 2, 4, false);
+    let (first, second) = sip_hash(&stable, 1, 3, true);
+    let stable_hash = first.wrapping_mul(3).wrapping_add(second);
+    [legacy_hash, stable_hash]
+        .iter()
+        .map(|hash| {
+            let hex = hash
+                .to_le_bytes()
+                .iter()
+                .fold(String::new(), |hex, byte| hex + &format!("{byte:02x}"));
+            format!("{host}-{hex}")
+        })
+        .collect()
+}
+
+// SipHash of data with zero key and provided number of compression and
+// finalization round, wide hash returns both half of 128 bit output
+fn sip_hash(data: &[u8], c_rounds: usize, d_rounds: usize, wide: bool) -> (u64, u64) {
+    let mut v = [
+        0x736f_6d65_7073_6575_u64,
+        0x646f_7261_6e64_6f6d_u64,
+        0x6c79_6765_6e65_7261_u64,
+        0x7465_6462_7974_6573_u64,
+    ];
+    if wide {
+        v[1] ^= 0xee;
+    }
+    let sip_round = |v: &mut [u64; 4]| {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    };
+    let chunks = data.chunks_exact(8);
+    let mut last = (data.len() as u64) << 56;
+    for (index, byte) in chunks.remainder().iter().enumerate() {
+        last |= u64::from(*byte) << (8 * index);
+    }
+    let words = chunks
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .chain(std::iter::once(last));
+    for word in words {
+        v[3] ^= word;
+        for _ in 0..c_rounds {
+            sip_round(&mut v);
+        }
+        v[0] ^= word;
+    }
+    v[2] ^= if wide { 0xee } else { 0xff };
+    for _ in 0..d_rounds {
+        sip_round(&mut v);
+    }
+    let first = v[0] ^ v[1] ^ v[2] ^ v[3];
+    v[1] ^= 0xdd;
+    for _ in 0..d_rounds {
+        sip_round(&mut v);
+    }
+    (first, v[0] ^ v[1] ^ v[2] ^ v[3])
+}
@@ -1,139 +1,197 @@
-use colored::*;
+use crate::{
+    crate_filter::FilterMode,
+    error::{io_error, TrimError},
+};
 use serde_derive::{Deserialize, Serialize};
-use std::{fs, io::Read, path::PathBuf};
+use std::{fs, path::Path};
 
-// Stores config file information
+/// Default value of flags which are used when flag is not passed or passed
+/// without value
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Policy {
+    max_age: Option<String>,
+    keep: Option<usize>,
+    budget: Option<String>,
+    format: Option<String>,
+}
+
+impl Policy {
+    /// return duration used by `--max-age` passed without value
+    #[must_use]
+    pub fn max_age(&self) -> Option<&str> {
+        self.max_age.as_deref()
+    }
+
+    /// return number of newest versions kept when `--keep` is not passed
+    #[must_use]
+    pub fn keep(&self) -> Option<usize> {
+        self.keep
+    }
+
+    /// return size used by `--budget` passed without value
+    #[must_use]
+    pub fn budget(&self) -> Option<&str> {
+        self.budget.as_deref()
+    }
+
+    /// return output format used when `--format` is not passed
+    #[must_use]
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+}
+
+/// Stores config file information
 #[derive(Serialize, Deserialize)]
-pub(crate) struct ConfigFile {
+pub struct ConfigFile {
+    #[serde(default)]
     directory: Vec<String>,
+    #[serde(default)]
     include: Vec<String>,
+    #[serde(default)]
     exclude: Vec<String>,
+    #[serde(default)]
+    mode: FilterMode,
+    #[serde(default)]
+    policy: Policy,
 }
 
 impl ConfigFile {
-    // Create new config file
-    pub(crate) fn new() -> Self {
+    /// Create new config file
+    #[must_use]
+    pub fn new() -> Self {
         Self {
             directory: Vec::new(),
             include: Vec::new(),
             exclude: Vec::new(),
+            mode: FilterMode::default(),
+            policy: Policy::default(),
+        }
+    }
+
+    /// Read toml config file present in path, empty config file is treated as
+    /// new config file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if config file cannot be read or parsed
+    pub fn init(config_file: &Path) -> Result<Self, TrimError> {
+        let buffer =
+            fs::read_to_string(config_file).map_err(io_error(config_file, "read config file"))?;
+        if buffer.trim().is_empty() {
+            Ok(Self::new())
+        } else {
+            toml::from_str(&buffer).map_err(|source| TrimError::Toml {
+                path: config_file.to_path_buf(),
+                source,
+            })
         }
     }
 
-    // return vector of directory value in config file
-    pub(crate) fn directory(&self) -> &Vec<String> {
+    /// Migrate json config file used by older version to toml config file,
+    /// json config file is left untouched
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if json config file cannot be read or parsed or toml
+    /// config file cannot be written
+    pub fn migrate(json_file: &Path, config_file: &Path) -> Result<Self, TrimError> {
+        let buffer =
+            fs::read_to_string(json_file).map_err(io_error(json_file, "read config file"))?;
+        let migrated = if buffer.trim().is_empty() {
+            Self::new()
+        } else {
+            serde_json::from_str(&buffer).map_err(|source| TrimError::Json {
+                path: json_file.to_path_buf(),
+                source,
+            })?
+        };
+        migrated.save(config_file)?;
+        Ok(migrated)
+    }
+
+    /// return vector of directory value in config file
+    #[must_use]
+    pub fn directory(&self) -> &Vec<String> {
         &self.directory
     }
 
-    // return vector of include value in config file
-    pub(crate) fn include(&self) -> &Vec<String> {
+    /// return vector of include value in config file
+    #[must_use]
+    pub fn include(&self) -> &Vec<String> {
         &self.include
     }
 
-    // return vector of exclude value in config file
-    pub(crate) fn exclude(&self) -> &Vec<String> {
+    /// return vector of exclude value in config file
+    #[must_use]
+    pub fn exclude(&self) -> &Vec<String> {
         &self.exclude
     }
-}
 
-// Function to modify config file or read config file
-pub(crate) fn modify_config_file(
-    file: &mut fs::File,
-    app: &clap::ArgMatches,
-    config_dir: &PathBuf,
-) -> ConfigFile {
-    let mut buffer = String::new();
-    file.read_to_string(&mut buffer)
-        .expect("failed to read config file string");
-    if buffer.is_empty() {
-        let initial_config = ConfigFile::new();
-        let serialize =
-            serde_json::to_string(&initial_config).expect("failed to convert ConfigFile to string");
-        buffer.push_str(&serialize)
-    }
-    let mut deserialize_config: ConfigFile =
-        serde_json::from_str(&buffer).expect("failed to convert string to ConfigFile");
-
-    // add working directory to config
-    if app.is_present("init") {
-        deserialize_config.directory.push(
-            std::env::current_dir()
-                .expect("Current working directory is invalid")
-                .to_str()
-                .expect("failed to convert current directory Path to str")
-                .to_string(),
-        );
-    }
-
-    // Add new value in config file
-    for &name in &["set directory", "exclude", "include"] {
-        if app.is_present(name) {
-            let value = app
-                .value_of(name)
-                .expect("No value is present for remove value from config file flag");
-            if name == "set directory" {
-                deserialize_config.directory.push(value.to_string());
-            }
-            if name == "exclude" {
-                deserialize_config.exclude.push(value.to_string());
-            }
-            if name == "include" {
-                deserialize_config.include.push(value.to_string());
-            }
-        }
+    /// return policy mode of include and exclude list in config file
+    #[must_use]
+    pub fn mode(&self) -> FilterMode {
+        self.mode
     }
 
-    // clear working directory from config file
-    if app.is_present("clear") {
-        let subcommand = app.subcommand_matches("clear").unwrap();
-        let dry_run = app.is_present("dry-run") || subcommand.is_present("dry run");
-        remove_item_crate(
-            &mut deserialize_config.directory,
-            std::env::current_dir()
-                .expect("Current working directory is invalid")
-                .to_str()
-                .expect("failed to convert current directory Path to str"),
-            dry_run,
-        );
-    }
-
-    // remove value from config file
-    if app.is_present("remove") {
-        let subcommand = app.subcommand_matches("remove").unwrap();
-        for &name in &["directory", "exclude", "include"] {
-            let dry_run = app.is_present("dry run") || subcommand.is_present("dry run");
-            if subcommand.is_present(name) {
-                let value = subcommand
-                    .value_of(name)
-                    .expect("No value is present for remove value from config file flag")
-                    .to_string();
-                if name == "directory" {
-                    remove_item_crate(&mut deserialize_config.directory, &value, dry_run);
-                }
-                if name == "exclude" {
-                    remove_item_crate(&mut deserialize_config.exclude, &value, dry_run);
-                }
-                if name == "include" {
-                    remove_item_crate(&mut deserialize_config.include, &value, dry_run);
-                }
-            }
-        }
+    /// set policy mode of include and exclude list in config file
+    pub fn set_mode(&mut self, mode: FilterMode) {
+        self.mode = mode;
     }
 
-    let serialized = serde_json::to_string_pretty(&deserialize_config)
-        .expect("ConfigFile cannot to converted to pretty json");
-    buffer.clear();
-    buffer.push_str(&serialized);
-    fs::write(config_dir, buffer).expect("Failed to write a value to config file");
-    deserialize_config
+    /// return policy section of config file
+    #[must_use]
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// add directory to config file
+    pub fn add_directory(&mut self, directory: &str) {
+        self.directory.push(directory.to_string());
+    }
+
+    /// add crate to include list of config file
+    pub fn add_include(&mut self, crate_name: &str) {
+        self.include.push(crate_name.to_string());
+    }
+
+    /// add crate to exclude list of config file
+    pub fn add_exclude(&mut self, crate_name: &str) {
+        self.exclude.push(crate_name.to_string());
+    }
+
+    /// remove directory from config file
+    pub fn remove_directory(&mut self, directory: &str) {
+        self.directory.retain(|data| data != directory);
+    }
+
+    /// remove crate from include list of config file
+    pub fn remove_include(&mut self, crate_name: &str) {
+        self.include.retain(|data| data != crate_name);
+    }
+
+    /// remove crate from exclude list of config file
+    pub fn remove_exclude(&mut self, crate_name: &str) {
+        self.exclude.retain(|data| data != crate_name);
+    }
+
+    /// write config file content as toml to provided path
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if config file cannot be serialized or written
+    pub fn save(&self, config_file: &Path) -> Result<(), TrimError> {
+        let serialized = toml::to_string(self).map_err(|source| TrimError::TomlSerialize {
+            path: config_file.to_path_buf(),
+            source,
+        })?;
+        fs::write(config_file, serialized).map_err(io_error(config_file, "write config file"))
+    }
 }
 
-// helper function to help in removing certain value from a config file
-fn remove_item_crate(data: &mut Vec<String>, value: &str, dry_run: bool) {
-    if dry_run {
-        println!("{} {} {:?}", "Dry run:".yellow(), "removed".red(), value);
-    } else {
-        data.retain(|data| data != value);
-        println!("{} {:?}", "Removed".red(), value);
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self::new()
     }
 }
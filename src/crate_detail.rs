@@ -1,130 +1,171 @@
-use std::collections::HashMap;
+use crate::{crate_id::CrateId, git_id::GitId};
+use std::{collections::HashMap, time::SystemTime};
 
-// stores different crate size and name information
-pub(crate) struct CrateDetail {
+/// stores different crate size and name information
+pub struct CrateDetail {
     bin: HashMap<String, u64>,
-    git_crates_source: HashMap<String, u64>,
-    registry_crates_source: HashMap<String, u64>,
-    git_crates_archive: HashMap<String, u64>,
-    registry_crates_archive: HashMap<String, u64>,
+    git_crates_source: HashMap<GitId, u64>,
+    registry_crates_source: HashMap<CrateId, u64>,
+    git_crates_archive: HashMap<GitId, u64>,
+    registry_crates_archive: HashMap<CrateId, u64>,
+    git_crates_last_used: HashMap<GitId, SystemTime>,
+    registry_crates_last_used: HashMap<CrateId, SystemTime>,
 }
 
 impl CrateDetail {
-    // create new crate detail struct
-    pub(crate) fn new() -> Self {
+    /// create new crate detail struct
+    #[must_use]
+    pub fn new() -> Self {
         Self {
             bin: HashMap::new(),
             git_crates_source: HashMap::new(),
             registry_crates_source: HashMap::new(),
             git_crates_archive: HashMap::new(),
             registry_crates_archive: HashMap::new(),
+            git_crates_last_used: HashMap::new(),
+            registry_crates_last_used: HashMap::new(),
         }
     }
 
-    // return bin crates size information
-    pub(crate) fn bin(&self) -> &HashMap<String, u64> {
+    /// return bin crates size information
+    #[must_use]
+    pub fn bin(&self) -> &HashMap<String, u64> {
         &self.bin
     }
 
-    // return git crates source size information
-    pub(crate) fn git_crates_source(&self) -> &HashMap<String, u64> {
+    /// return git crates source size information
+    #[must_use]
+    pub fn git_crates_source(&self) -> &HashMap<GitId, u64> {
         &self.git_crates_source
     }
 
-    // return registry crates source size information
-    pub(crate) fn registry_crates_source(&self) -> &HashMap<String, u64> {
+    /// return registry crates source size information
+    #[must_use]
+    pub fn registry_crates_source(&self) -> &HashMap<CrateId, u64> {
         &self.registry_crates_source
     }
 
-    // return git crates archive size information
-    pub(crate) fn git_crates_archive(&self) -> &HashMap<String, u64> {
+    /// return git crates archive size information
+    #[must_use]
+    pub fn git_crates_archive(&self) -> &HashMap<GitId, u64> {
         &self.git_crates_archive
     }
 
-    // return registry crates archive size information
-    pub(crate) fn registry_crates_archive(&self) -> &HashMap<String, u64> {
+    /// return registry crates archive size information
+    #[must_use]
+    pub fn registry_crates_archive(&self) -> &HashMap<CrateId, u64> {
         &self.registry_crates_archive
     }
 
-    // add bin information to CrateDetail
-    pub(crate) fn add_bin(&mut self, bin_name: String, size: u64) {
+    /// return git crates last used time information
+    #[must_use]
+    pub fn git_crates_last_used(&self) -> &HashMap<GitId, SystemTime> {
+        &self.git_crates_last_used
+    }
+
+    /// return registry crates last used time information
+    #[must_use]
+    pub fn registry_crates_last_used(&self) -> &HashMap<CrateId, SystemTime> {
+        &self.registry_crates_last_used
+    }
+
+    /// add bin information to `CrateDetail`
+    pub fn add_bin(&mut self, bin_name: String, size: u64) {
         self.bin.insert(bin_name, size);
     }
 
-    // add git crate source information to CrateDetail
-    pub(crate) fn add_git_crate_source(&mut self, crate_name: String, size: u64) {
-        self.git_crates_source.insert(crate_name, size);
+    /// add git crate source information to `CrateDetail`
+    pub fn add_git_crate_source(&mut self, git_id: GitId, size: u64) {
+        self.git_crates_source.insert(git_id, size);
+    }
+
+    /// add registry crate source information to `CrateDetail`
+    pub fn add_registry_crate_source(&mut self, crate_id: CrateId, size: u64) {
+        self.registry_crates_source.insert(crate_id, size);
     }
 
-    // add registry crate source information to CrateDetail
-    pub(crate) fn add_registry_crate_source(&mut self, crate_name: String, size: u64) {
-        self.registry_crates_source.insert(crate_name, size);
+    /// add git crate archive information to `CrateDetail`
+    pub fn add_git_crate_archive(&mut self, git_id: GitId, size: u64) {
+        self.git_crates_archive.insert(git_id, size);
     }
 
-    // add git crate archive information to CrateDetail
-    pub(crate) fn add_git_crate_archive(&mut self, crate_name: String, size: u64) {
-        self.git_crates_archive.insert(crate_name, size);
+    /// add registry crate archive information to `CrateDetail`
+    pub fn add_registry_crate_archive(&mut self, crate_id: CrateId, size: u64) {
+        self.registry_crates_archive.insert(crate_id, size);
     }
 
-    // add registry crate archive information to CrateDetail
-    pub(crate) fn add_registry_crate_archive(&mut self, crate_name: String, size: u64) {
-        self.registry_crates_archive.insert(crate_name, size);
+    /// add git crate last used time to `CrateDetail`, if crate already have last
+    /// used time latest one is kept
+    pub fn add_git_crate_last_used(&mut self, git_id: GitId, time: SystemTime) {
+        let last_used = self.git_crates_last_used.entry(git_id).or_insert(time);
+        *last_used = (*last_used).max(time);
     }
 
-    // find size of certain git crate source
-    pub(crate) fn find_size_git_source(&self, crate_name: &str) -> f64 {
-        if let Some(size) = self.git_crates_source.get(crate_name) {
+    /// add registry crate last used time to `CrateDetail`, if crate already have
+    /// last used time latest one is kept
+    pub fn add_registry_crate_last_used(&mut self, crate_id: CrateId, time: SystemTime) {
+        let last_used = self
+            .registry_crates_last_used
+            .entry(crate_id)
+            .or_insert(time);
+        *last_used = (*last_used).max(time);
+    }
+
+    /// find size of certain git crate source
+    #[must_use]
+    pub fn find_size_git_source(&self, git_id: &GitId) -> f64 {
+        if let Some(size) = self.git_crates_source.get(git_id) {
             (*size as f64) / 1000_f64.powf(2.0)
         } else {
             0.0
         }
     }
 
-    // find size of certain registry source
-    pub(crate) fn find_size_registry_source(&self, crate_name: &str) -> f64 {
-        if let Some(size) = self.registry_crates_source.get(crate_name) {
+    /// find size of certain registry source
+    #[must_use]
+    pub fn find_size_registry_source(&self, crate_id: &CrateId) -> f64 {
+        if let Some(size) = self.registry_crates_source.get(crate_id) {
             (*size as f64) / 1000_f64.powf(2.0)
         } else {
             0.0
         }
     }
 
-    // find size of certain git crate archive
-    pub(crate) fn find_size_git_archive(&self, crate_name: &str) -> f64 {
-        if let Some(size) = self.git_crates_archive.get(crate_name) {
+    /// find size of certain git crate archive
+    #[must_use]
+    pub fn find_size_git_archive(&self, git_id: &GitId) -> f64 {
+        if let Some(size) = self.git_crates_archive.get(git_id) {
             (*size as f64) / 1000_f64.powf(2.0)
         } else {
             0.0
         }
     }
 
-    // find size of certain registry archive
-    pub(crate) fn find_size_registry_archive(&self, crate_name: &str) -> f64 {
-        if let Some(size) = self.registry_crates_archive.get(crate_name) {
+    /// find size of certain registry archive
+    #[must_use]
+    pub fn find_size_registry_archive(&self, crate_id: &CrateId) -> f64 {
+        if let Some(size) = self.registry_crates_archive.get(crate_id) {
             (*size as f64) / 1000_f64.powf(2.0)
         } else {
             0.0
         }
     }
 
-    // return certain git crate total size
-    pub(crate) fn find_size_git_all(&self, crate_name: &str) -> f64 {
-        self.find_size_git_archive(crate_name) + self.find_size_git_source(crate_name)
+    /// return certain git crate total size
+    #[must_use]
+    pub fn find_size_git_all(&self, git_id: &GitId) -> f64 {
+        self.find_size_git_archive(git_id) + self.find_size_git_source(git_id)
     }
 
-    // return certain registry crate total size
-    pub(crate) fn find_size_registry_all(&self, crate_name: &str) -> f64 {
-        self.find_size_registry_archive(crate_name) + self.find_size_registry_source(crate_name)
+    /// return certain registry crate total size
+    #[must_use]
+    pub fn find_size_registry_all(&self, crate_id: &CrateId) -> f64 {
+        self.find_size_registry_archive(crate_id) + self.find_size_registry_source(crate_id)
     }
+}
 
-    // find crate size if location/title is given
-    pub(crate) fn find(&self, crate_name: &str, location: &str) -> f64 {
-        if location.contains("REGISTRY") {
-            self.find_size_registry_all(crate_name)
-        } else if location.contains("GIT") {
-            self.find_size_git_all(crate_name)
-        } else {
-            0.0
-        }
+impl Default for CrateDetail {
+    fn default() -> Self {
+        Self::new()
     }
 }
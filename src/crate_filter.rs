@@ -0,0 +1,408 @@
+use crate::{error::TrimError, list_crate, ConfigFile, CrateId, GitId, ProjectConfig};
+use regex::Regex;
+use semver::{Version, VersionReq};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+// prefix of rule value which is treated as regular expression
+const REGEX_PREFIX: &str = "re:";
+
+// separator between name and semver requirement of rule such as `serde@^1`
+const VERSION_SEPARATOR: char = '@';
+
+/// Policy which decides which crates can be removed. Exclude rule always takes
+/// priority over include rule in both mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// only crates matched by include rule are removed
+    Allowlist,
+    /// all crates except crates matched by exclude rule are removed
+    #[default]
+    Denylist,
+}
+
+impl FilterMode {
+    /// parse mode from its name, return none if name is not valid mode
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allowlist" => Some(Self::Allowlist),
+            "denylist" => Some(Self::Denylist),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Allowlist => "allowlist",
+            Self::Denylist => "denylist",
+        };
+        write!(f, "{mode}")
+    }
+}
+
+/// Location from where rule is read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleSource {
+    /// include or exclude list of config file
+    ConfigFile,
+    /// `TRIM_INCLUDE` or `TRIM_EXCLUDE` environment variable
+    Environment(&'static str),
+    /// protect list of project local `.cargo-trim.toml` file
+    ProjectFile,
+}
+
+impl fmt::Display for RuleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConfigFile => write!(f, "config file"),
+            Self::Environment(variable) => write!(f, "${variable}"),
+            Self::ProjectFile => write!(f, ".cargo-trim.toml"),
+        }
+    }
+}
+
+/// Syntax of rule value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleKind {
+    /// value is matched exactly
+    Exact,
+    /// value containing `*`, `?` or `[` is matched as glob pattern
+    Glob,
+    /// value prefixed with `re:` is matched as regular expression
+    Regex,
+}
+
+impl fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Exact => "exact",
+            Self::Glob => "glob",
+            Self::Regex => "regex",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+enum Matcher {
+    Exact(String),
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+/// Single include or exclude rule. Rule matches crate when its value matches
+/// either full name such as `serde-1.0.1` or simple name such as `serde` of
+/// crate. Glob pattern should match whole name while regular expression can
+/// match any part of name unless it is anchored. Rule with semver requirement
+/// such as `serde@^1` or `syn@>=1.0, <2` only matches simple name of registry
+/// crate whose version satisfies requirement
+pub struct CrateRule {
+    value: String,
+    source: RuleSource,
+    matcher: Matcher,
+    version_req: Option<VersionReq>,
+}
+
+impl CrateRule {
+    /// parse rule value, return error if glob pattern or regular expression
+    /// is invalid
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if semver requirement, glob pattern or regular
+    /// expression is invalid
+    pub fn parse(
+        value: &str,
+        source: RuleSource,
+        argument: &'static str,
+    ) -> Result<Self, TrimError> {
+        let invalid_value = || TrimError::InvalidValue {
+            argument,
+            value: value.to_string(),
+        };
+        let (name, version_req) = if value.starts_with(REGEX_PREFIX) {
+            // regular expression can contain `@` itself so only last `@` which is
+            // followed by valid semver requirement starts requirement
+            match value
+                .rsplit_once(VERSION_SEPARATOR)
+                .and_then(|(name, requirement)| {
+                    VersionReq::parse(requirement)
+                        .ok()
+                        .map(|version_req| (name, version_req))
+                }) {
+                Some((name, version_req)) => (name, Some(version_req)),
+                None => (value, None),
+            }
+        } else {
+            match value.split_once(VERSION_SEPARATOR) {
+                Some((name, requirement)) => (
+                    name,
+                    Some(VersionReq::parse(requirement).map_err(|_| invalid_value())?),
+                ),
+                None => (value, None),
+            }
+        };
+        let matcher = if let Some(regex) = name.strip_prefix(REGEX_PREFIX) {
+            Matcher::Regex(Regex::new(regex).map_err(|_| invalid_value())?)
+        } else if name.contains(['*', '?', '[']) {
+            Matcher::Glob(glob::Pattern::new(name).map_err(|_| invalid_value())?)
+        } else {
+            Matcher::Exact(name.to_string())
+        };
+        Ok(Self {
+            value: value.to_string(),
+            source,
+            matcher,
+            version_req,
+        })
+    }
+
+    /// return value of rule as written in config file or environment variable
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// return source of rule
+    #[must_use]
+    pub fn source(&self) -> RuleSource {
+        self.source
+    }
+
+    /// return syntax of rule
+    #[must_use]
+    pub fn kind(&self) -> RuleKind {
+        match self.matcher {
+            Matcher::Exact(_) => RuleKind::Exact,
+            Matcher::Glob(_) => RuleKind::Glob,
+            Matcher::Regex(_) => RuleKind::Regex,
+        }
+    }
+
+    /// return semver requirement of rule
+    #[must_use]
+    pub fn version_req(&self) -> Option<&VersionReq> {
+        self.version_req.as_ref()
+    }
+
+    /// check if rule matches crate, return name of crate which is matched.
+    /// Version is only present for registry crate
+    #[must_use]
+    pub fn matches<'a>(
+        &self,
+        full_name: &'a str,
+        simple_name: &'a str,
+        version: Option<&Version>,
+    ) -> Option<&'a str> {
+        if let Some(version_req) = &self.version_req {
+            return version
+                .filter(|version| version_req.matches(version))
+                .and(Some(simple_name))
+                .filter(|name| self.matches_name(name));
+        }
+        [full_name, simple_name]
+            .iter()
+            .copied()
+            .find(|name| self.matches_name(name))
+    }
+
+    // check if rule matches single name
+    fn matches_name(&self, name: &str) -> bool {
+        match &self.matcher {
+            Matcher::Exact(value) => value == name,
+            Matcher::Glob(pattern) => pattern.matches(name),
+            Matcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Include and exclude rules of config file and `TRIM_INCLUDE` and
+/// `TRIM_EXCLUDE` environment variable along with policy mode of config file
+pub struct CrateFilter {
+    mode: FilterMode,
+    include: Vec<CrateRule>,
+    exclude: Vec<CrateRule>,
+}
+
+impl CrateFilter {
+    /// create filter from config file and environment variable, return error if
+    /// any rule is invalid
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any include or exclude rule is invalid
+    pub fn new(config_file: &ConfigFile) -> Result<Self, TrimError> {
+        Ok(Self {
+            mode: config_file.mode(),
+            include: parse_rules(config_file.include(), "TRIM_INCLUDE", "include rule")?,
+            exclude: parse_rules(config_file.exclude(), "TRIM_EXCLUDE", "exclude rule")?,
+        })
+    }
+
+    /// add protected crates of project config files as exclude rules, return
+    /// error if any rule is invalid
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any protect rule is invalid
+    pub fn add_project_rules(
+        &mut self,
+        project_configs: &[ProjectConfig],
+    ) -> Result<(), TrimError> {
+        for project_config in project_configs {
+            for value in project_config.protect() {
+                self.exclude.push(CrateRule::parse(
+                    value,
+                    RuleSource::ProjectFile,
+                    "protect rule",
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    /// return policy mode of filter
+    #[must_use]
+    pub fn mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    /// return all include rules
+    #[must_use]
+    pub fn include(&self) -> &[CrateRule] {
+        &self.include
+    }
+
+    /// return all exclude rules
+    #[must_use]
+    pub fn exclude(&self) -> &[CrateRule] {
+        &self.exclude
+    }
+
+    /// return first include rule which matches crate
+    #[must_use]
+    pub fn included_by(
+        &self,
+        full_name: &str,
+        simple_name: &str,
+        version: Option<&Version>,
+    ) -> Option<&CrateRule> {
+        self.include
+            .iter()
+            .find(|rule| rule.matches(full_name, simple_name, version).is_some())
+    }
+
+    /// return first exclude rule which matches crate
+    #[must_use]
+    pub fn excluded_by(
+        &self,
+        full_name: &str,
+        simple_name: &str,
+        version: Option<&Version>,
+    ) -> Option<&CrateRule> {
+        self.exclude
+            .iter()
+            .find(|rule| rule.matches(full_name, simple_name, version).is_some())
+    }
+
+    /// check if crate is excluded
+    #[must_use]
+    pub fn is_excluded(
+        &self,
+        full_name: &str,
+        simple_name: &str,
+        version: Option<&Version>,
+    ) -> bool {
+        self.excluded_by(full_name, simple_name, version).is_some()
+    }
+
+    /// check if crate can be removed. Excluded crate is never removed, in
+    /// allowlist mode crate also needs to be included
+    #[must_use]
+    pub fn follow(&self, full_name: &str, simple_name: &str, version: Option<&Version>) -> bool {
+        if self.is_excluded(full_name, simple_name, version) {
+            return false;
+        }
+        match self.mode {
+            FilterMode::Allowlist => self.included_by(full_name, simple_name, version).is_some(),
+            FilterMode::Denylist => true,
+        }
+    }
+
+    /// return first exclude rule which matches installed registry crate
+    #[must_use]
+    pub fn registry_excluded_by(&self, crate_id: &CrateId) -> Option<&CrateRule> {
+        self.excluded_by(
+            &crate_id.to_string(),
+            crate_id.name(),
+            Some(crate_id.version()),
+        )
+    }
+
+    /// return first exclude rule which matches installed git crate, git crate
+    /// doesn't have version so it is never matched by rule with semver
+    /// requirement
+    #[must_use]
+    pub fn git_excluded_by(&self, git_id: &GitId) -> Option<&CrateRule> {
+        self.excluded_by(&git_id.to_string(), git_id.name(), None)
+    }
+
+    /// check if installed registry crate can be removed
+    #[must_use]
+    pub fn follow_registry(&self, crate_id: &CrateId) -> bool {
+        self.follow(
+            &crate_id.to_string(),
+            crate_id.name(),
+            Some(crate_id.version()),
+        )
+    }
+
+    /// check if installed git crate can be removed
+    #[must_use]
+    pub fn follow_git(&self, git_id: &GitId) -> bool {
+        self.follow(&git_id.to_string(), git_id.name(), None)
+    }
+}
+
+// parse rules of config file list followed by environment variable list
+fn parse_rules(
+    config_list: &[String],
+    variable: &'static str,
+    argument: &'static str,
+) -> Result<Vec<CrateRule>, TrimError> {
+    let mut rules = Vec::new();
+    for value in config_list {
+        rules.push(CrateRule::parse(value, RuleSource::ConfigFile, argument)?);
+    }
+    for value in join_version_req(list_crate::env_list(variable)) {
+        rules.push(CrateRule::parse(
+            &value,
+            RuleSource::Environment(variable),
+            argument,
+        )?);
+    }
+    Ok(rules)
+}
+
+// join whitespace separated values of environment variable back into single
+// rule when they are separated after `,` of semver requirement so rule such as
+// `syn@>=1.0, <2` is not split into `syn@>=1.0,` and `<2`
+pub(crate) fn join_version_req(values: Vec<String>) -> Vec<String> {
+    let mut rules: Vec<String> = Vec::new();
+    for value in values {
+        match rules.last_mut() {
+            Some(last)
+                if last.contains(VERSION_SEPARATOR)
+                    && (last.ends_with(',') || value.starts_with(',')) =>
+            {
+                last.push(' ');
+                last.push_str(&value);
+            }
+            _ => rules.push(value),
+        }
+    }
+    rules
+}
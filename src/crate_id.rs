@@ -0,0 +1,62 @@
+use semver::Version;
+use std::fmt;
+
+/// Identity of registry crate made up of its name, semver version and registry
+/// directory such as `github.com-1ecc6299db9ec823`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CrateId {
+    name: String,
+    version: Version,
+    registry: String,
+}
+
+impl CrateId {
+    /// create new `CrateId`
+    #[must_use]
+    pub fn new(name: &str, version: Version, registry: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+            registry: registry.to_string(),
+        }
+    }
+
+    /// parse `CrateId` from `name-version` format used by registry src directory
+    /// and cache archive. Since crate name cannot contain `.` but can contain `-`
+    /// followed by digit and version can contain `-` in pre release, name is
+    /// split at first `-` after which rest of value is valid semver version
+    #[must_use]
+    pub fn parse(full_name: &str, registry: &str) -> Option<Self> {
+        full_name.match_indices('-').find_map(|(position, _)| {
+            let (name, version) = (&full_name[..position], &full_name[position + 1..]);
+            Version::parse(version)
+                .ok()
+                .filter(|_| !name.is_empty())
+                .map(|version| Self::new(name, version, registry))
+        })
+    }
+
+    /// return name of crate
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// return version of crate
+    #[must_use]
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// return name of registry directory which contains crate
+    #[must_use]
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+}
+
+impl fmt::Display for CrateId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.name, self.version)
+    }
+}
@@ -1,4 +1,4 @@
-use clap::{App, AppSettings, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
 
 // Create all list of subcommand options flag using clap
 pub(super) fn app() -> App<'static, 'static> {
@@ -14,6 +14,35 @@ pub(super) fn app() -> App<'static, 'static> {
         .help("Clean up all .cargo/registry follow config file data");
     let all_list = all.clone().help("list out all installed crate");
 
+    let budget = Arg::with_name("budget")
+        .long("budget")
+        .help(
+            "Remove least recently used registry source, registry archive and git checkout until \
+             .cargo/registry and .cargo/git fits in size such as 500MB or 2GiB [default: budget \
+             policy of conf file]",
+        )
+        .takes_value(true)
+        .min_values(0)
+        .value_name("size");
+
+    let by_project = Arg::with_name("by project").long("by-project").help(
+        "list out size of crates used by each project directory along with size used only by it",
+    );
+
+    let cargo_home = Arg::with_name("cargo home")
+        .long("cargo-home")
+        .help("Cargo home directory to use [default: $CARGO_HOME or $HOME/.cargo]")
+        .takes_value(true)
+        .value_name("path")
+        .global(true);
+
+    let config_file = Arg::with_name("config file")
+        .long("config")
+        .help("Config file to use [default: cargo_trim_config.toml of config directory]")
+        .takes_value(true)
+        .value_name("path")
+        .global(true);
+
     let clear_config = Arg::with_name("clear config")
         .short("c")
         .long("clear")
@@ -55,6 +84,17 @@ pub(super) fn app() -> App<'static, 'static> {
         .long("force")
         .help("Force clear cache without reading conf file");
 
+    let format = Arg::with_name("format")
+        .long("format")
+        .help(
+            "Output format of list, query, top and removal report [default: format policy of conf \
+             file or table]",
+        )
+        .takes_value(true)
+        .value_name("format")
+        .possible_values(&["json", "table"])
+        .global(true);
+
     let git_compress = Arg::with_name("git compress")
         .short("g")
         .long("gc")
@@ -62,6 +102,31 @@ pub(super) fn app() -> App<'static, 'static> {
         .takes_value(true)
         .possible_values(&["all", "index", "git", "git-checkout", "git-db"]);
 
+    let history_crate = Arg::with_name("crate")
+        .long("crate")
+        .help("Only show run which removed crate with provided name such as serde or serde-1.0.1")
+        .takes_value(true)
+        .value_name("name");
+
+    let history_last = Arg::with_name("last")
+        .long("last")
+        .help("Only show provided number of latest run")
+        .takes_value(true)
+        .value_name("number");
+
+    let history_since = Arg::with_name("since")
+        .long("since")
+        .help(
+            "Only show run executed in provided duration such as 30d or 12w [units: s, m, h, d, w]",
+        )
+        .takes_value(true)
+        .value_name("duration");
+
+    let history_summary = Arg::with_name("summary")
+        .short("s")
+        .long("summary")
+        .help("Only show cumulative space reclaimed by run");
+
     let include = Arg::with_name("include").short("i").long("include");
     let include_config = include.clone().help("Query about include data");
     let include_remove = include
@@ -80,6 +145,22 @@ pub(super) fn app() -> App<'static, 'static> {
         .takes_value(true)
         .value_name("crate");
 
+    let keep = Arg::with_name("keep")
+        .long("keep")
+        .help(
+            "Number of newest versions of each crate which are not treated as old by old clean \
+             and old list [default: keep policy of conf file or 1]",
+        )
+        .takes_value(true)
+        .value_name("n");
+
+    let keep_per_major = Arg::with_name("keep per major")
+        .long("keep-per-major")
+        .help(
+            "Keep newest versions in each semver compatible range such as latest 0.3.x and 1.x \
+             instead of newest versions of crate",
+        );
+
     let light_cleanup = Arg::with_name("light cleanup").short("l").long("light");
     let light_cleanup_trim = light_cleanup.clone().help(
         "Light cleanup repos by removing git checkout and registry source but stores git db and \
@@ -94,6 +175,54 @@ pub(super) fn app() -> App<'static, 'static> {
          compilation",
     );
 
+    let list_trash = Arg::with_name("list")
+        .short("l")
+        .long("list")
+        .help("List out trash run along with their size and age");
+
+    let max_age = Arg::with_name("max age")
+        .long("max-age")
+        .takes_value(true)
+        .min_values(0)
+        .value_name("duration");
+    let max_age_trim = max_age.clone().help(
+        "Clean registry source, registry archive and git checkout not used in provided duration \
+         such as 30d or 12w [units: s, m, h, d, w] [default: max-age policy of conf file]",
+    );
+    let max_age_git = max_age.clone().help(
+        "Clean git checkout not used in provided duration such as 30d or 12w [units: s, m, h, d, \
+         w] [default: max-age policy of conf file]",
+    );
+    let max_age_registry = max_age.clone().help(
+        "Clean registry source and archive not used in provided duration such as 30d or 12w \
+         [units: s, m, h, d, w] [default: max-age policy of conf file]",
+    );
+    let max_age_list = max_age.clone().help(
+        "list out crates not used in provided duration such as 30d or 12w [default: max-age \
+             policy of conf file]",
+    );
+
+    let max_depth = Arg::with_name("max depth")
+        .long("max-depth")
+        .help("Maximum depth of directory scanned for Cargo.toml inside project directory")
+        .takes_value(true)
+        .value_name("n")
+        .global(true);
+
+    let no_wait = Arg::with_name("no wait")
+        .long("no-wait")
+        .conflicts_with("wait")
+        .help("Fail instead of waiting when other cargo process holds package cache lock");
+
+    let older_than = Arg::with_name("older than")
+        .long("older-than")
+        .help(
+            "Only purge trash run older than provided duration such as 30d or 12w [units: s, m, \
+             h, d, w]",
+        )
+        .takes_value(true)
+        .value_name("duration");
+
     let old = Arg::with_name("old")
         .short("o")
         .long("old")
@@ -117,6 +246,12 @@ pub(super) fn app() -> App<'static, 'static> {
              generated till now use cargo trim -u to guarantee your all project generate lock file",
         );
 
+    let purge_all = Arg::with_name("all")
+        .short("a")
+        .long("all")
+        .conflicts_with("older than")
+        .help("Purge all trash run, required when --older-than is not passed");
+
     let query_size = Arg::with_name("query size").short("q").long("query");
     let query_size_trim = query_size
         .clone()
@@ -128,6 +263,15 @@ pub(super) fn app() -> App<'static, 'static> {
         .clone()
         .help("Return size of different .cargo/registry cache folders");
 
+    let registry_name = Arg::with_name("registry name")
+        .long("registry")
+        .help(
+            "Only operate on registry crates of provided registry name, index url or directory \
+             name such as crates-io, git crates are skipped",
+        )
+        .takes_value(true)
+        .value_name("name|url");
+
     let remove_crate = Arg::with_name("remove-crate")
         .short("r")
         .long("remove")
@@ -136,6 +280,22 @@ pub(super) fn app() -> App<'static, 'static> {
         .takes_value(true)
         .value_name("crate");
 
+    let mode = Arg::with_name("mode")
+        .short("m")
+        .long("mode")
+        .help("Query about policy mode of include and exclude rules");
+
+    let set_mode = Arg::with_name("set mode")
+        .long("set-mode")
+        .help(
+            "Set policy mode of include and exclude rules in conf file, allowlist only removes \
+             included crates while denylist removes all crates except excluded crates [default: \
+             denylist]",
+        )
+        .takes_value(true)
+        .value_name("mode")
+        .possible_values(&["allowlist", "denylist"]);
+
     let set_directory = Arg::with_name("set directory")
         .short("s")
         .multiple(true)
@@ -153,6 +313,24 @@ pub(super) fn app() -> App<'static, 'static> {
         .index(1)
         .possible_values(&["bash", "zsh", "fish", "powershell", "elvish"]);
 
+    let trash_id = Arg::with_name("id")
+        .help("Id of trash run to restore [default: latest trash run]")
+        .index(1);
+
+    let trash_mode = Arg::with_name("trash mode").long("trash").help(
+        "Move removed path to $CARGO_HOME/.trim-trash instead of deleting them so they can be \
+         restored with undo subcommand",
+    );
+
+    let test_crate = Arg::with_name("test")
+        .long("test")
+        .help(
+            "Show which include and exclude rule matches crate such as serde or serde-1.0.1 and \
+             whether it is removed",
+        )
+        .takes_value(true)
+        .value_name("crate");
+
     let top_crate = Arg::with_name("top crates")
         .short("t")
         .long("top")
@@ -178,6 +356,10 @@ pub(super) fn app() -> App<'static, 'static> {
         .long("use")
         .help("list out used crates");
 
+    let wait = Arg::with_name("wait").long("wait").help(
+        "Wait for other cargo process to release package cache lock before removing [default]",
+    );
+
     let wipe = Arg::with_name("wipe")
         .short("w")
         .long("wipe")
@@ -194,35 +376,58 @@ pub(super) fn app() -> App<'static, 'static> {
         .takes_value(true)
         .value_name("folder");
 
+    let why_crate = Arg::with_name("crate")
+        .help("Crate to explain such as serde or serde@1.0.1")
+        .required(true)
+        .index(1);
+
     App::new(env!("CARGO_PKG_NAME"))
         .bin_name("cargo")
         .version(env!("CARGO_PKG_VERSION"))
-        .settings(&[AppSettings::GlobalVersion, AppSettings::SubcommandRequired])
-        .global_setting(AppSettings::ArgRequiredElseHelp)
+        .settings(&[
+            AppSettings::ArgRequiredElseHelp,
+            AppSettings::GlobalVersion,
+            AppSettings::SubcommandRequired,
+        ])
         .subcommand(
             SubCommand::with_name("trim")
+                .setting(AppSettings::ArgRequiredElseHelp)
                 .author(env!("CARGO_PKG_AUTHORS"))
                 .about(env!("CARGO_PKG_DESCRIPTION"))
                 .args(&[
                     all_trim,
+                    budget,
+                    cargo_home,
                     clear_config,
+                    config_file,
                     exclude_conf,
                     dry_run.clone(),
                     force_remove.clone(),
+                    format,
                     git_compress,
                     include_conf,
+                    keep.clone(),
+                    keep_per_major.clone(),
                     light_cleanup_trim,
+                    max_age_trim,
+                    max_depth,
+                    no_wait.clone(),
                     old_clean.clone(),
                     orphan_clean.clone(),
                     query_size_trim,
+                    registry_name.clone(),
                     remove_crate.clone(),
                     set_directory,
+                    set_mode,
                     top_crate_trim,
+                    trash_mode.clone(),
                     update,
+                    wait.clone(),
                     wipe,
                 ])
                 .subcommand(
                     SubCommand::with_name("init")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about(
                             "Initialize current working directory as cargo trim directory [alias: \
                              \"in\"]",
@@ -231,6 +436,7 @@ pub(super) fn app() -> App<'static, 'static> {
                 )
                 .subcommand(
                     SubCommand::with_name("clear")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about(
                             "Clear current working directory from cargo cache config [alias: \
                              \"clr\"]",
@@ -240,28 +446,43 @@ pub(super) fn app() -> App<'static, 'static> {
                 )
                 .subcommand(
                     SubCommand::with_name("config")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about("Query config file data [alias: \"c\"]")
                         .alias("c")
-                        .args(&[directory_config, exclude_config, include_config]),
+                        .args(&[
+                            directory_config,
+                            exclude_config,
+                            include_config,
+                            mode,
+                            test_crate,
+                        ]),
                 )
                 .subcommand(
                     SubCommand::with_name("git")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about("Perform operation only to git related cache file [alias: \"g\"]")
                         .alias("g")
                         .args(&[
                             all_git,
                             dry_run.clone(),
                             force_remove.clone(),
+                            keep.clone(),
+                            keep_per_major.clone(),
                             light_cleanup_git,
+                            max_age_git,
+                            no_wait.clone(),
                             old_clean.clone(),
                             orphan_clean.clone(),
                             query_size_git,
                             remove_crate.clone(),
                             top_crate_git,
+                            trash_mode.clone(),
+                            wait.clone(),
                         ]),
                 )
                 .subcommand(
                     SubCommand::with_name("registry")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about(
                             "Perform operation only to registry related cache file [alias: \
                              \"reg\"]",
@@ -271,22 +492,47 @@ pub(super) fn app() -> App<'static, 'static> {
                             all_registry,
                             dry_run.clone(),
                             force_remove.clone(),
+                            keep.clone(),
+                            keep_per_major.clone(),
                             light_cleanup_registry,
+                            max_age_registry,
+                            no_wait.clone(),
                             old_clean.clone(),
                             orphan_clean.clone(),
                             query_size_registry,
+                            registry_name.clone(),
                             remove_crate.clone(),
                             top_crates_registry,
+                            trash_mode,
+                            wait.clone(),
                         ]),
                 )
+                .subcommand(
+                    SubCommand::with_name("history")
+                        .about("List out previous run and space reclaimed by them [alias: \"h\"]")
+                        .alias("h")
+                        .args(&[history_crate, history_last, history_since, history_summary]),
+                )
                 .subcommand(
                     SubCommand::with_name("list")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about("List out crates [alias: \"l\"]")
                         .alias("l")
-                        .args(&[all_list, old, orphan, used]),
+                        .args(&[
+                            all_list,
+                            by_project,
+                            keep,
+                            keep_per_major,
+                            max_age_list,
+                            old,
+                            orphan,
+                            registry_name,
+                            used,
+                        ]),
                 )
                 .subcommand(
                     SubCommand::with_name("remove")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about("Remove values from config file [alias: \"rm\"]")
                         .alias("rm")
                         .args(&[
@@ -296,8 +542,43 @@ pub(super) fn app() -> App<'static, 'static> {
                             include_remove,
                         ]),
                 )
+                .subcommand(
+                    SubCommand::with_name("trash")
+                        .setting(AppSettings::ArgRequiredElseHelp)
+                        .about("Inspect and empty $CARGO_HOME/.trim-trash [alias: \"t\"]")
+                        .alias("t")
+                        .arg(list_trash)
+                        .subcommand(
+                            SubCommand::with_name("purge")
+                                .about("Permanently remove trash run [alias: \"p\"]")
+                                .alias("p")
+                                .args(&[older_than, purge_all])
+                                .group(
+                                    ArgGroup::with_name("purge range")
+                                        .args(&["all", "older than"])
+                                        .required(true),
+                                ),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("undo")
+                        .about("Restore path moved to trash by trash run [alias: \"u\"]")
+                        .alias("u")
+                        .args(&[no_wait, trash_id, wait]),
+                )
+                .subcommand(
+                    SubCommand::with_name("why")
+                        .setting(AppSettings::ArgRequiredElseHelp)
+                        .about(
+                            "Show dependency path of project pulling in crate and rule protecting \
+                             it [alias: \"w\"]",
+                        )
+                        .alias("w")
+                        .arg(why_crate),
+                )
                 .subcommand(
                     SubCommand::with_name("completions")
+                        .setting(AppSettings::ArgRequiredElseHelp)
                         .about("Generate tab-completions scripts for shell [alias: \"comp\"]")
                         .alias("comp")
                         .arg(shell_type),
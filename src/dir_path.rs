@@ -1,12 +1,16 @@
-use std::{
-    fs,
-    path::{Path, PathBuf},
+use crate::{
+    config_file::ConfigFile,
+    error::{io_error, TrimError},
 };
+use std::{env, fs, path::PathBuf};
 
-// Struct for storing Directory path
-pub(crate) struct DirPath {
+/// Struct for storing Directory path
+pub struct DirPath {
+    cargo_home: PathBuf,
     bin_dir: PathBuf,
     config_dir: PathBuf,
+    history_file: PathBuf,
+    scan_cache: PathBuf,
     git_dir: PathBuf,
     checkout_dir: PathBuf,
     db_dir: PathBuf,
@@ -14,22 +18,50 @@ pub(crate) struct DirPath {
     cache_dir: PathBuf,
     index_dir: PathBuf,
     src_dir: PathBuf,
+    trash_dir: PathBuf,
 }
 
 impl DirPath {
-    // set directory path
-    pub(crate) fn set_dir_path() -> Self {
+    /// set directory path, cargo home is resolved at runtime either from provided
+    /// value or same way as cargo does. Config file is either provided file or
+    /// `cargo_trim_config.toml` of config directory which is migrated from
+    /// `cargo_trim_config.json` when only json config file is present
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if cargo home or config directory cannot be resolved or
+    /// config file cannot be created
+    #[allow(clippy::implicit_clone)]
+    pub fn set_dir_path(
+        cargo_home: Option<&str>,
+        config_file: Option<&str>,
+    ) -> Result<Self, TrimError> {
         // set config file directory path
-        let mut config_dir = dirs::config_dir()
-            .expect("Cannot get config directory location it may be not supported OS");
-        config_dir.push("cargo_trim_config.json");
+        let config_dir = if let Some(config_file) = config_file {
+            absolute_path(PathBuf::from(config_file))?
+        } else {
+            let mut config_dir = dirs::config_dir().ok_or(TrimError::Directory("config"))?;
+            config_dir.push("cargo_trim_config.toml");
+            let json_file = config_dir.with_file_name("cargo_trim_config.json");
+            if !config_dir.exists() && json_file.exists() {
+                ConfigFile::migrate(&json_file, &config_dir)?;
+            }
+            config_dir
+        };
 
         // If config file does not exists create one config file
         if !config_dir.exists() {
-            fs::File::create(config_dir.to_str().unwrap()).expect("Failed to create config file");
+            fs::File::create(&config_dir).map_err(io_error(&config_dir, "create config file"))?;
         }
 
-        let home_dir = Path::new(env!("CARGO_HOME"));
+        // history file is stored next to config file
+        let history_file = config_dir.with_file_name("cargo_trim_history.jsonl");
+
+        // scan cache is stored next to config file
+        let scan_cache = config_dir.with_file_name("cargo_trim_scan_cache.json");
+
+        let cargo_home = resolve_cargo_home(cargo_home)?;
+        let home_dir = cargo_home.as_path();
 
         // set bin directory path
         let mut bin_dir = home_dir.to_path_buf();
@@ -57,9 +89,16 @@ impl DirPath {
         let mut index_dir = registry_dir.to_path_buf();
         index_dir.push("index");
 
-        Self {
+        // set trash dir path
+        let mut trash_dir = home_dir.to_path_buf();
+        trash_dir.push(".trim-trash");
+
+        Ok(Self {
+            cargo_home,
             bin_dir,
             config_dir,
+            history_file,
+            scan_cache,
             git_dir,
             checkout_dir,
             db_dir,
@@ -67,51 +106,115 @@ impl DirPath {
             cache_dir,
             index_dir,
             src_dir,
-        }
+            trash_dir,
+        })
     }
 
-    // return path of bin dir
-    pub(crate) fn bin_dir(&self) -> &PathBuf {
+    /// return path of cargo home
+    #[must_use]
+    pub fn cargo_home(&self) -> &PathBuf {
+        &self.cargo_home
+    }
+
+    /// return path of bin dir
+    #[must_use]
+    pub fn bin_dir(&self) -> &PathBuf {
         &self.bin_dir
     }
 
-    // return path of config dir
-    pub(crate) fn config_dir(&self) -> &PathBuf {
+    /// return path of config dir
+    #[must_use]
+    pub fn config_dir(&self) -> &PathBuf {
         &self.config_dir
     }
 
-    // return path of git dir
-    pub(crate) fn git_dir(&self) -> &PathBuf {
+    /// return path of history file
+    #[must_use]
+    pub fn history_file(&self) -> &PathBuf {
+        &self.history_file
+    }
+
+    /// return path of scan cache file
+    #[must_use]
+    pub fn scan_cache(&self) -> &PathBuf {
+        &self.scan_cache
+    }
+
+    /// return path of git dir
+    #[must_use]
+    pub fn git_dir(&self) -> &PathBuf {
         &self.git_dir
     }
 
-    // return path of checkout dir
-    pub(crate) fn checkout_dir(&self) -> &PathBuf {
+    /// return path of checkout dir
+    #[must_use]
+    pub fn checkout_dir(&self) -> &PathBuf {
         &self.checkout_dir
     }
 
-    // return path of db dir
-    pub(crate) fn db_dir(&self) -> &PathBuf {
+    /// return path of db dir
+    #[must_use]
+    pub fn db_dir(&self) -> &PathBuf {
         &self.db_dir
     }
 
-    // return path of registry dir
-    pub(crate) fn registry_dir(&self) -> &PathBuf {
+    /// return path of registry dir
+    #[must_use]
+    pub fn registry_dir(&self) -> &PathBuf {
         &self.registry_dir
     }
 
-    // return path of cache dir
-    pub(crate) fn cache_dir(&self) -> &PathBuf {
+    /// return path of cache dir
+    #[must_use]
+    pub fn cache_dir(&self) -> &PathBuf {
         &self.cache_dir
     }
 
-    // return path of index dir
-    pub(crate) fn index_dir(&self) -> &PathBuf {
+    /// return path of index dir
+    #[must_use]
+    pub fn index_dir(&self) -> &PathBuf {
         &self.index_dir
     }
 
-    // return path of src dir
-    pub(crate) fn src_dir(&self) -> &PathBuf {
+    /// return path of src dir
+    #[must_use]
+    pub fn src_dir(&self) -> &PathBuf {
         &self.src_dir
     }
+
+    /// return path of trash dir
+    #[must_use]
+    pub fn trash_dir(&self) -> &PathBuf {
+        &self.trash_dir
+    }
+}
+
+// resolve cargo home directory, use provided path if present otherwise use
+// $CARGO_HOME and fallback to $HOME/.cargo
+fn resolve_cargo_home(cargo_home: Option<&str>) -> Result<PathBuf, TrimError> {
+    let cargo_home = cargo_home
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("CARGO_HOME").map(PathBuf::from))
+        .filter(|path| !path.as_os_str().is_empty());
+    if let Some(path) = cargo_home {
+        absolute_path(path)
+    } else {
+        let mut home_dir = dirs::home_dir().ok_or(TrimError::Directory("home"))?;
+        home_dir.push(".cargo");
+        Ok(home_dir)
+    }
+}
+
+// resolve relative path against current directory
+fn absolute_path(path: PathBuf) -> Result<PathBuf, TrimError> {
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    Ok(env::current_dir()
+        .map_err(|source| TrimError::Io {
+            path: path.clone(),
+            operation: "resolve current directory for",
+            source,
+        })?
+        .join(path))
 }
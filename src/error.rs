@@ -0,0 +1,169 @@
+use std::{error::Error, fmt, io, path::Path, path::PathBuf};
+
+/// Error which can occur while scanning or trimming cargo home
+#[derive(Debug)]
+pub enum TrimError {
+    /// io operation failed for a certain path
+    Io {
+        /// path on which operation was performed
+        path: PathBuf,
+        /// operation which was performed on path
+        operation: &'static str,
+        /// underlying io error
+        source: io::Error,
+    },
+    /// toml file cannot be parsed
+    Toml {
+        /// path of toml file
+        path: PathBuf,
+        /// underlying toml error
+        source: toml::de::Error,
+    },
+    /// toml file cannot be serialized
+    TomlSerialize {
+        /// path of toml file
+        path: PathBuf,
+        /// underlying toml error
+        source: toml::ser::Error,
+    },
+    /// json file cannot be parsed or serialized
+    Json {
+        /// path of json file
+        path: PathBuf,
+        /// underlying json error
+        source: serde_json::Error,
+    },
+    /// external command cannot be executed
+    Command {
+        /// command which was executed
+        command: String,
+        /// directory in which command was executed
+        path: PathBuf,
+        /// underlying io error
+        source: io::Error,
+    },
+    /// git operation failed for repository
+    Git {
+        /// path of git repository
+        path: PathBuf,
+        /// underlying git error
+        source: git2::Error,
+    },
+    /// package cache is locked by other cargo process
+    Locked {
+        /// path of package cache lock file
+        path: PathBuf,
+    },
+    /// required directory location cannot be determined
+    Directory(&'static str),
+    /// Cargo.lock file content is not supported
+    LockFile {
+        /// path of lock file
+        path: PathBuf,
+        /// description of unsupported content
+        message: String,
+    },
+    /// invalid value passed for argument
+    InvalidValue {
+        /// name of argument
+        argument: &'static str,
+        /// value passed to argument
+        value: String,
+    },
+}
+
+impl TrimError {
+    /// return exit code which should be used by process for error, exit code
+    /// follows sysexits convention
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::InvalidValue { .. } => 64,
+            Self::Toml { .. }
+            | Self::TomlSerialize { .. }
+            | Self::Json { .. }
+            | Self::Git { .. }
+            | Self::LockFile { .. } => 65,
+            Self::Command { .. } => 69,
+            Self::Io { .. } => 74,
+            Self::Locked { .. } => 75,
+            Self::Directory(_) => 78,
+        }
+    }
+}
+
+impl fmt::Display for TrimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io {
+                path,
+                operation,
+                source,
+            } => write!(f, "failed to {} {}: {}", operation, path.display(), source),
+            Self::Toml { path, source } => {
+                write!(f, "failed to parse toml {}: {}", path.display(), source)
+            }
+            Self::TomlSerialize { path, source } => {
+                write!(f, "failed to serialize toml {}: {}", path.display(), source)
+            }
+            Self::Json { path, source } => {
+                write!(f, "failed to parse json {}: {}", path.display(), source)
+            }
+            Self::Command {
+                command,
+                path,
+                source,
+            } => write!(
+                f,
+                "failed to execute `{}` in {}: {}",
+                command,
+                path.display(),
+                source
+            ),
+            Self::Git { path, source } => {
+                write!(f, "git operation failed for {}: {}", path.display(), source)
+            }
+            Self::Locked { path } => write!(
+                f,
+                "package cache {} is locked by other cargo process, rerun without --no-wait to \
+                 wait for it",
+                path.display()
+            ),
+            Self::Directory(name) => write!(f, "cannot determine {name} directory location"),
+            Self::LockFile { path, message } => {
+                write!(f, "unsupported lock file {}: {}", path.display(), message)
+            }
+            Self::InvalidValue { argument, value } => {
+                write!(f, "invalid value {value:?} for {argument}")
+            }
+        }
+    }
+}
+
+impl Error for TrimError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } | Self::Command { source, .. } => Some(source),
+            Self::Toml { source, .. } => Some(source),
+            Self::TomlSerialize { source, .. } => Some(source),
+            Self::Json { source, .. } => Some(source),
+            Self::Git { source, .. } => Some(source),
+            Self::Locked { .. }
+            | Self::Directory(_)
+            | Self::LockFile { .. }
+            | Self::InvalidValue { .. } => None,
+        }
+    }
+}
+
+// create closure which converts io error to TrimError for path and operation
+pub(crate) fn io_error<'a>(
+    path: &'a Path,
+    operation: &'static str,
+) -> impl FnOnce(io::Error) -> TrimError + 'a {
+    move |source| TrimError::Io {
+        path: path.to_path_buf(),
+        operation,
+        source,
+    }
+}
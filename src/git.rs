@@ -0,0 +1,232 @@
+use crate::{
+    error::{io_error, TrimError},
+    utils::{file_name, get_size, read_dir},
+};
+use git2::Repository;
+use std::{fs, path::Path};
+
+// get full commit id of latest commit of git db repo by reading its refs, if
+// branch or tag is provided get latest commit of that branch or tag
+pub(crate) fn latest_rev(path_db: &Path, branch: Option<&str>) -> Result<String, TrimError> {
+    let repo = Repository::open(path_db).map_err(git_error(path_db))?;
+    // cargo fetches branch and tag of db as remote branch and tag, HEAD of bare
+    // db can point to branch which doesn't exist so remote HEAD is also tried
+    let reference_names = match branch {
+        Some(branch) => vec![
+            format!("refs/remotes/origin/{branch}"),
+            format!("refs/heads/{branch}"),
+            format!("refs/tags/{branch}"),
+        ],
+        None => vec!["HEAD".to_string(), "refs/remotes/origin/HEAD".to_string()],
+    };
+    for reference_name in &reference_names {
+        let Ok(reference) = repo.find_reference(reference_name) else {
+            continue;
+        };
+        let Ok(commit) = reference.peel_to_commit() else {
+            continue;
+        };
+        return Ok(commit.id().to_string());
+    }
+    Err(TrimError::Git {
+        path: path_db.to_path_buf(),
+        source: git2::Error::from_str(&format!(
+            "cannot resolve {} to commit",
+            branch.unwrap_or("HEAD")
+        )),
+    })
+}
+
+// get full commit id of commit present in git db repo from full or abbreviated
+// commit id or other revision, return none if db doesn't contain commit
+pub(crate) fn find_commit(path_db: &Path, rev: &str) -> Result<Option<String>, TrimError> {
+    let repo = Repository::open(path_db).map_err(git_error(path_db))?;
+    Ok(repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .ok()
+        .map(|commit| commit.id().to_string()))
+}
+
+/// Result of each step of git compress of repository
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompressSummary {
+    expired_refs: usize,
+    packed_objects: usize,
+    removed_packs: usize,
+    saved: u64,
+}
+
+impl CompressSummary {
+    /// return number of refs whose reflog is expired
+    #[must_use]
+    pub fn expired_refs(&self) -> usize {
+        self.expired_refs
+    }
+
+    /// return number of objects packed into single pack
+    #[must_use]
+    pub fn packed_objects(&self) -> usize {
+        self.packed_objects
+    }
+
+    /// return number of old packs removed
+    #[must_use]
+    pub fn removed_packs(&self) -> usize {
+        self.removed_packs
+    }
+
+    /// return number of bytes saved
+    #[must_use]
+    pub fn saved(&self) -> u64 {
+        self.saved
+    }
+}
+
+/// git compress a git repository by expiring its reflog and repacking all
+/// objects reachable from its refs into single pack, return result of each
+/// step
+///
+/// # Errors
+///
+/// Returns an error if repository cannot be read or packed or if its old
+/// objects cannot be removed
+pub fn git_compress(repo_path: &Path) -> Result<CompressSummary, TrimError> {
+    let repo = Repository::open(repo_path).map_err(git_error(repo_path))?;
+    let size_before = get_size(repo_path)?;
+    let mut summary = CompressSummary::default();
+
+    // Remove history of all refs which will help in remove dangling commits
+    let mut reference_names = vec!["HEAD".to_string()];
+    for reference in repo.references().map_err(git_error(repo_path))? {
+        let reference = reference.map_err(git_error(repo_path))?;
+        if let Some(name) = reference.name() {
+            reference_names.push(name.to_string());
+        }
+    }
+    for reference_name in &reference_names {
+        repo.reflog_delete(reference_name)
+            .map_err(git_error(repo_path))?;
+    }
+    summary.expired_refs = reference_names.len();
+
+    // pack all objects reachable from refs into one pack
+    let mut revwalk = repo.revwalk().map_err(git_error(repo_path))?;
+    let mut tags = Vec::new();
+    for reference in repo.references().map_err(git_error(repo_path))? {
+        let reference = reference.map_err(git_error(repo_path))?;
+        // annotated tag object is not part of commit history so it is
+        // inserted separately
+        if let Some(target) = reference.target() {
+            if repo.find_tag(target).is_ok() {
+                tags.push(target);
+            }
+        }
+        if let Ok(commit) = reference.peel_to_commit() {
+            revwalk.push(commit.id()).map_err(git_error(repo_path))?;
+        }
+    }
+    if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+        revwalk.push(head.id()).map_err(git_error(repo_path))?;
+    }
+    let mut pack_builder = repo.packbuilder().map_err(git_error(repo_path))?;
+    pack_builder
+        .insert_walk(&mut revwalk)
+        .map_err(git_error(repo_path))?;
+    for tag in tags {
+        pack_builder
+            .insert_object(tag, None)
+            .map_err(git_error(repo_path))?;
+    }
+    summary.packed_objects = pack_builder.object_count();
+    let objects_dir = repo.path().join("objects");
+    let pack_dir = objects_dir.join("pack");
+    if summary.packed_objects > 0 {
+        fs::create_dir_all(&pack_dir).map_err(io_error(&pack_dir, "create directory"))?;
+        pack_builder
+            .write(&pack_dir, 0)
+            .map_err(git_error(repo_path))?;
+    }
+
+    // remove loose objects and old packs which are now present in new pack
+    if summary.packed_objects > 0 {
+        // without name of new pack every existing pack would be treated as
+        // redundant so compression is aborted instead
+        let pack_name = pack_builder
+            .name()
+            .map(|name| format!("pack-{name}"))
+            .ok_or_else(|| TrimError::Git {
+                path: repo_path.to_path_buf(),
+                source: git2::Error::from_str("cannot determine name of written pack"),
+            })?;
+        summary.removed_packs = remove_redundant_objects(&objects_dir, &pack_name)?;
+    }
+    summary.saved = size_before.saturating_sub(get_size(repo_path)?);
+    Ok(summary)
+}
+
+// remove loose object directory and pack other than new pack, pack which has
+// .keep file is never removed. Multi pack index refers to removed packs so it
+// is removed and pack list of dumb transport is rewritten. Return number of
+// removed pack
+fn remove_redundant_objects(objects_dir: &Path, pack_name: &str) -> Result<usize, TrimError> {
+    for path in read_dir(objects_dir)? {
+        let name = file_name(&path);
+        if path.is_dir() && name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+            fs::remove_dir_all(&path).map_err(io_error(&path, "remove directory"))?;
+        }
+    }
+    let pack_dir = objects_dir.join("pack");
+    let mut removed_packs = 0;
+    for path in &read_dir(&pack_dir)? {
+        if file_name(path).starts_with("multi-pack-index") {
+            if path.is_dir() {
+                fs::remove_dir_all(path).map_err(io_error(path, "remove directory"))?;
+            } else {
+                fs::remove_file(path).map_err(io_error(path, "remove file"))?;
+            }
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if stem == pack_name
+            || !stem.starts_with("pack-")
+            || pack_dir.join(format!("{stem}.keep")).exists()
+        {
+            continue;
+        }
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "pack")
+        {
+            removed_packs += 1;
+        }
+        fs::remove_file(path).map_err(io_error(path, "remove file"))?;
+    }
+    let info_packs = objects_dir.join("info").join("packs");
+    if info_packs.exists() {
+        let mut packs = read_dir(&pack_dir)?
+            .iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "pack")
+            })
+            .map(|path| file_name(path))
+            .filter(|name| name.starts_with("pack-"))
+            .map(|name| format!("P {name}\n"))
+            .collect::<Vec<_>>();
+        packs.sort();
+        packs.push("\n".to_string());
+        fs::write(&info_packs, packs.concat()).map_err(io_error(&info_packs, "write file"))?;
+    }
+    Ok(removed_packs)
+}
+
+// create closure which converts git error to TrimError for repository path
+fn git_error(path: &Path) -> impl FnOnce(git2::Error) -> TrimError + '_ {
+    move |source| TrimError::Git {
+        path: path.to_path_buf(),
+        source,
+    }
+}
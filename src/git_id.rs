@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Identity of git crate made up of its git db directory such as
+/// `serde-1ecc6299db9ec823` and revision of checkout. Revision of checkout is
+/// prefix of full commit id and git db itself doesn't have revision
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GitId {
+    db: String,
+    rev: Option<String>,
+}
+
+impl GitId {
+    /// create new `GitId`, revision should be `None` for git db
+    pub fn new(db: &str, rev: Option<&str>) -> Self {
+        Self {
+            db: db.to_string(),
+            rev: rev.map(ToString::to_string),
+        }
+    }
+
+    /// return name of git db directory which contains crate
+    #[must_use]
+    pub fn db(&self) -> &str {
+        &self.db
+    }
+
+    /// return name of repo i.e git db directory without hash of its url
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.db.rsplit_once('-').map_or(&self.db, |(name, _)| name)
+    }
+
+    /// return revision of checkout, git db doesn't have revision
+    #[must_use]
+    pub fn rev(&self) -> Option<&str> {
+        self.rev.as_deref()
+    }
+
+    /// check if crate is checkout of provided full commit id
+    #[must_use]
+    pub fn is_checkout_of(&self, commit: &str) -> bool {
+        self.rev()
+            .is_some_and(|rev| !rev.is_empty() && commit.starts_with(rev))
+    }
+}
+
+impl fmt::Display for GitId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.name(), self.rev().unwrap_or("HEAD"))
+    }
+}
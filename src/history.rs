@@ -0,0 +1,265 @@
+use crate::{
+    error::{io_error, TrimError},
+    utils::unix_time,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Single path removed by run of cargo-trim
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryPath {
+    path: PathBuf,
+    category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crate_name: Option<String>,
+    size: u64,
+}
+
+impl HistoryPath {
+    /// return removed path
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// return category of path such as registry archive or git checkout
+    #[must_use]
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    /// return name of crate which is removed, path removed as whole directory
+    /// such as wiped folder doesn't have crate name
+    #[must_use]
+    pub fn crate_name(&self) -> Option<&str> {
+        self.crate_name.as_deref()
+    }
+
+    /// return size of path in bytes
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Record of single run of cargo-trim which modified cargo home
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    timestamp: u64,
+    args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trash_id: Option<String>,
+    removed: Vec<HistoryPath>,
+    freed: BTreeMap<String, u64>,
+}
+
+impl HistoryEntry {
+    /// create new empty entry for run invoked with provided arguments
+    #[must_use]
+    pub fn new(args: Vec<String>) -> Self {
+        Self {
+            timestamp: unix_time(SystemTime::now()),
+            args,
+            trash_id: None,
+            removed: Vec::new(),
+            freed: BTreeMap::new(),
+        }
+    }
+
+    /// set id of trash run, path of run are moved to trash instead of
+    /// removing them so their size is quarantined instead of freed
+    pub fn set_trash_id(&mut self, trash_id: &str) {
+        self.trash_id = Some(trash_id.to_string());
+    }
+
+    /// add removed path of category, git compress doesn't remove path but
+    /// its saved size is also added
+    pub fn add(&mut self, path: &Path, category: &str, crate_name: Option<&str>, size: u64) {
+        self.removed.push(HistoryPath {
+            path: path.to_path_buf(),
+            category: category.to_string(),
+            crate_name: crate_name.map(ToString::to_string),
+            size,
+        });
+        *self.freed.entry(category.to_string()).or_insert(0) += size;
+    }
+
+    /// return time at which run was invoked
+    #[must_use]
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.timestamp)
+    }
+
+    /// return arguments passed to run
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// return id of trash run if path were moved to trash
+    #[must_use]
+    pub fn trash_id(&self) -> Option<&str> {
+        self.trash_id.as_deref()
+    }
+
+    /// return all path removed by run
+    #[must_use]
+    pub fn removed(&self) -> &[HistoryPath] {
+        &self.removed
+    }
+
+    /// return bytes freed by run for each category
+    #[must_use]
+    pub fn freed(&self) -> &BTreeMap<String, u64> {
+        &self.freed
+    }
+
+    /// return total bytes freed by run
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.freed.values().sum()
+    }
+
+    /// check if run didn't remove anything
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+    }
+
+    /// check if run removed any version of crate with provided name or full
+    /// name such as `serde` or `serde-1.0.1`
+    #[must_use]
+    pub fn removed_crate(&self, name: &str) -> bool {
+        self.removed.iter().any(|history_path| {
+            history_path.crate_name().is_some_and(|crate_name| {
+                crate_name == name
+                    || crate_name
+                        .strip_prefix(name)
+                        .is_some_and(|version| version.starts_with('-'))
+            })
+        })
+    }
+}
+
+/// Cumulative space reclaimed by multiple run
+#[derive(Clone, Default, Serialize)]
+pub struct HistorySummary {
+    runs: usize,
+    freed: u64,
+    quarantined: u64,
+    categories: BTreeMap<String, u64>,
+}
+
+impl HistorySummary {
+    /// create summary of provided entries, size of run which moved path to trash
+    /// is counted as quarantined instead of freed
+    #[must_use]
+    pub fn new(entries: &[HistoryEntry]) -> Self {
+        let mut summary = Self::default();
+        for entry in entries {
+            summary.runs += 1;
+            if entry.trash_id.is_some() {
+                summary.quarantined += entry.total();
+                continue;
+            }
+            summary.freed += entry.total();
+            for (category, size) in &entry.freed {
+                *summary.categories.entry(category.clone()).or_insert(0) += size;
+            }
+        }
+        summary
+    }
+
+    /// return number of run
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// return total bytes freed by all run
+    #[must_use]
+    pub fn freed(&self) -> u64 {
+        self.freed
+    }
+
+    /// return total bytes moved to trash by all run
+    #[must_use]
+    pub fn quarantined(&self) -> u64 {
+        self.quarantined
+    }
+
+    /// return total bytes freed by all run for each category
+    #[must_use]
+    pub fn categories(&self) -> &BTreeMap<String, u64> {
+        &self.categories
+    }
+}
+
+/// History file present next to config file which contains record of every run
+/// which modified cargo home. Each line of file is single json entry so run is
+/// appended without reading whole file
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    /// create new `History` for provided history file, file is created when
+    /// first entry is appended
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// append entry to history file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if entry cannot be serialized or written to history file
+    pub fn append(&self, entry: &HistoryEntry) -> Result<(), TrimError> {
+        let mut line = serde_json::to_string(entry).map_err(|source| TrimError::Json {
+            path: self.path.clone(),
+            source,
+        })?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error(&self.path, "open history file"))?;
+        file.write_all(line.as_bytes())
+            .map_err(io_error(&self.path, "write history file"))
+    }
+
+    /// read all entries of history file from oldest to newest. Line which
+    /// cannot be parsed is skipped and its error is pushed to errors
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if history file cannot be read
+    pub fn entries(&self, errors: &mut Vec<TrimError>) -> Result<Vec<HistoryEntry>, TrimError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let buffer =
+            fs::read_to_string(&self.path).map_err(io_error(&self.path, "read history file"))?;
+        let mut entries = Vec::new();
+        for line in buffer.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<HistoryEntry>(line) {
+                Ok(entry) => entries.push(entry),
+                Err(source) => errors.push(TrimError::Json {
+                    path: self.path.clone(),
+                    source,
+                }),
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,89 @@
+//! Library to scan and trim `$CARGO_HOME` cache
+//!
+//! Library is used by `cargo trim` binary but can also be used by other tools
+//! to list out crates present in cargo home directory along with their size
+//! and remove them.
+//!
+//! ```no_run
+//! use cargo_trim::{ConfigFile, CrateDetail, CrateList, DirPath, Scanner};
+//!
+//! # fn main() -> Result<(), cargo_trim::TrimError> {
+//! let dir_path = DirPath::set_dir_path(None, None)?;
+//! let config_file = ConfigFile::init(dir_path.config_dir())?;
+//! let mut crate_detail = CrateDetail::new();
+//! let mut errors = Vec::new();
+//! let crate_list = CrateList::create_list(
+//!     &dir_path,
+//!     &config_file,
+//!     &Scanner::new(),
+//!     &mut crate_detail,
+//!     &mut errors,
+//! );
+//! for crate_id in crate_list.old_registry() {
+//!     println!("{} {}", crate_id, crate_detail.find_size_registry_all(crate_id));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(
+    missing_docs,
+    unreachable_pub,
+    anonymous_parameters,
+    bare_trait_objects
+)]
+#![deny(unsafe_code)]
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::cast_precision_loss)]
+
+mod cargo_config;
+mod config_file;
+mod crate_detail;
+mod crate_filter;
+mod crate_id;
+mod dir_path;
+mod error;
+mod git;
+mod git_id;
+mod history;
+mod list_crate;
+mod lock_file;
+mod package_cache_lock;
+mod project_config;
+mod registry_index;
+mod removal_plan;
+mod report;
+mod scanner;
+#[cfg(test)]
+mod test;
+mod trash;
+mod utils;
+mod workspace;
+
+pub use crate::{
+    cargo_config::CargoConfig,
+    config_file::{ConfigFile, Policy},
+    crate_detail::CrateDetail,
+    crate_filter::{CrateFilter, CrateRule, FilterMode, RuleKind, RuleSource},
+    crate_id::CrateId,
+    dir_path::DirPath,
+    error::TrimError,
+    git::{git_compress, CompressSummary},
+    git_id::GitId,
+    history::{History, HistoryEntry, HistoryPath, HistorySummary},
+    list_crate::{CargoTomlLocation, CrateList, KeepPolicy, ProjectUsage},
+    lock_file::{LockDependency, LockFile, LockPackage},
+    package_cache_lock::PackageCacheLock,
+    project_config::ProjectConfig,
+    registry_index::{IndexKind, RegistryIndex},
+    removal_plan::{EntryKind, PlanEntry, Reason, RemovalPlan},
+    report::{
+        BudgetReport, CrateReport, HistoryReport, ProjectReport, QueryReport, RegistryQuery,
+        RemovalReport, Report, TopReport, REPORT_VERSION,
+    },
+    scanner::Scanner,
+    trash::{Trash, TrashEntry, TrashRun},
+    utils::{delete_folder, delete_index_cache, parse_duration, parse_size, update_cargo_lock},
+    workspace::Workspace,
+};
@@ -1,87 +1,198 @@
-use crate::{config_file::ConfigFile, crate_detail::CrateDetail, dir_path::DirPath};
-use fs_extra::dir::get_size;
-use serde_derive::Deserialize;
+use crate::{
+    cargo_config::{registry_dir_names, registry_host, url_host},
+    config_file::ConfigFile,
+    crate_detail::CrateDetail,
+    crate_id::CrateId,
+    dir_path::DirPath,
+    error::TrimError,
+    git::{find_commit, latest_rev},
+    git_id::GitId,
+    lock_file::LockFile,
+    project_config::ProjectConfig,
+    scanner::Scanner,
+    utils::{file_name, get_size, last_used, read_dir},
+    workspace::{resolve_workspaces, Workspace},
+};
+use semver::Version;
 use std::{
-    env, fs,
-    io::prelude::*,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env,
     path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
 
-// struct store Cargo.lock file location
-pub(crate) struct CargoTomlLocation {
+/// Policy which decides how many versions of same crate are kept and not
+/// listed as old
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeepPolicy {
+    count: usize,
+    per_major: bool,
+}
+
+impl KeepPolicy {
+    /// create new keep policy which keeps count newest versions of each crate,
+    /// if per major is true count newest versions are kept in each semver
+    /// compatible range of crate instead
+    #[must_use]
+    pub fn new(count: usize, per_major: bool) -> Self {
+        Self { count, per_major }
+    }
+
+    /// return number of newest versions kept
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// return whether versions are kept per semver compatible range
+    #[must_use]
+    pub fn per_major(&self) -> bool {
+        self.per_major
+    }
+}
+
+impl Default for KeepPolicy {
+    fn default() -> Self {
+        Self::new(1, false)
+    }
+}
+
+/// struct store Cargo.toml file location along with workspace and project
+/// config file found while scanning
+pub struct CargoTomlLocation {
     path: Vec<PathBuf>,
+    workspace: Vec<Workspace>,
+    project_config: Vec<ProjectConfig>,
 }
 
 impl CargoTomlLocation {
     pub(crate) fn new() -> Self {
-        Self { path: Vec::new() }
+        Self {
+            path: Vec::new(),
+            workspace: Vec::new(),
+            project_config: Vec::new(),
+        }
     }
 
     pub(crate) fn add_path(&mut self, path: PathBuf) {
         self.path.push(path);
     }
 
-    pub(crate) fn append(&mut self, mut lock_location: Self) {
-        self.path.append(&mut lock_location.path);
+    pub(crate) fn add_project_config(&mut self, project_config: Option<ProjectConfig>) {
+        self.project_config.extend(project_config);
+    }
+
+    pub(crate) fn set_workspaces(&mut self, workspace: Vec<Workspace>) {
+        self.workspace = workspace;
     }
 
-    pub(crate) fn location_path(&self) -> &Vec<PathBuf> {
+    /// return list of directory which contains Cargo.toml file
+    #[must_use]
+    pub fn location_path(&self) -> &Vec<PathBuf> {
         &self.path
     }
-}
 
-#[derive(Clone, Deserialize)]
-struct LockData {
-    package: Option<Vec<Package>>,
-}
+    /// return list of workspace of directory which contains Cargo.toml file,
+    /// each workspace is read through Cargo.lock present in its root
+    #[must_use]
+    pub fn workspaces(&self) -> &[Workspace] {
+        &self.workspace
+    }
 
-impl LockData {
-    fn package(&self) -> Option<Vec<Package>> {
-        self.package.to_owned()
+    /// return root directory of all workspaces
+    #[must_use]
+    pub fn workspace_roots(&self) -> Vec<PathBuf> {
+        self.workspace
+            .iter()
+            .map(|workspace| workspace.root().clone())
+            .collect()
+    }
+
+    /// return list of project config file found inside scanned directory
+    #[must_use]
+    pub fn project_configs(&self) -> &[ProjectConfig] {
+        &self.project_config
     }
 }
 
-#[derive(Clone, Deserialize)]
-struct Package {
-    name: String,
-    version: String,
-    source: Option<String>,
+/// Size in bytes of crates used by project directory. Exclusive size is size of
+/// crates which are not used by any other project directory i.e size freed
+/// when project is deleted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectUsage {
+    directory: PathBuf,
+    registry_size: u64,
+    registry_exclusive_size: u64,
+    git_size: u64,
+    git_exclusive_size: u64,
 }
 
-impl Package {
-    fn name(&self) -> &str {
-        &self.name
+impl ProjectUsage {
+    /// return project directory
+    #[must_use]
+    pub fn directory(&self) -> &PathBuf {
+        &self.directory
     }
 
-    fn version(&self) -> &str {
-        &self.version
+    /// return size of registry crates used by project
+    #[must_use]
+    pub fn registry_size(&self) -> u64 {
+        self.registry_size
     }
 
-    fn source(&self) -> Option<String> {
-        self.source.to_owned()
+    /// return size of registry crates used only by project
+    #[must_use]
+    pub fn registry_exclusive_size(&self) -> u64 {
+        self.registry_exclusive_size
+    }
+
+    /// return size of git crates used by project
+    #[must_use]
+    pub fn git_size(&self) -> u64 {
+        self.git_size
+    }
+
+    /// return size of git crates used only by project
+    #[must_use]
+    pub fn git_exclusive_size(&self) -> u64 {
+        self.git_exclusive_size
     }
 }
 
-// struct to store all crate list detail with its type
-pub(crate) struct CrateList {
+// crate along with root of workspaces using it
+type UsedBy<T> = BTreeMap<T, Vec<PathBuf>>;
+
+/// struct to store all crate list detail with its type
+pub struct CrateList {
     installed_bin: Vec<String>,
-    installed_crate_registry: Vec<String>,
-    installed_crate_git: Vec<String>,
-    old_crate_registry: Vec<String>,
-    old_crate_git: Vec<String>,
-    used_crate_registry: Vec<String>,
-    used_crate_git: Vec<String>,
-    orphan_crate_registry: Vec<String>,
-    orphan_crate_git: Vec<String>,
+    installed_crate_registry: Vec<CrateId>,
+    installed_crate_git: Vec<GitId>,
+    old_crate_registry: Vec<CrateId>,
+    old_crate_git: Vec<GitId>,
+    latest_crate_git: Vec<GitId>,
+    unresolved_crate_git: Vec<String>,
+    used_crate_registry: Vec<CrateId>,
+    used_crate_git: Vec<GitId>,
+    orphan_crate_registry: Vec<CrateId>,
+    orphan_crate_git: Vec<GitId>,
+    registry_workspace: UsedBy<CrateId>,
+    git_workspace: UsedBy<GitId>,
     cargo_toml_location: CargoTomlLocation,
+    project_directory: Vec<PathBuf>,
+    lock_files: Vec<LockFile>,
+    warnings: Vec<TrimError>,
 }
 
 impl CrateList {
-    // create list of all types of crate present in directory
-    pub(crate) fn create_list(
+    /// create list of all types of crate present in directory, project
+    /// directory is scanned with scanner. Entry which cannot be read is skipped
+    /// and its error is pushed to errors
+    pub fn create_list(
         dir_path: &DirPath,
         config_file: &ConfigFile,
+        scanner: &Scanner,
         crate_detail: &mut CrateDetail,
+        errors: &mut Vec<TrimError>,
     ) -> Self {
         let bin_dir = dir_path.bin_dir().as_path();
         let cache_dir = dir_path.cache_dir();
@@ -89,118 +200,68 @@ impl CrateList {
         let checkout_dir = dir_path.checkout_dir().as_path();
         let db_dir = dir_path.db_dir().as_path();
         // list out installed crates
-        let installed_bin = get_installed_bin(bin_dir, crate_detail);
+        let installed_bin = get_installed_bin(bin_dir, crate_detail, errors);
         let installed_crate_registry =
-            get_installed_crate_registry(src_dir, cache_dir, crate_detail);
-        let installed_crate_git = get_installed_crate_git(checkout_dir, db_dir, crate_detail);
-
-        // list old registry crate
-        let mut old_crate_registry = Vec::new();
-        let mut version_removed_crate = remove_version(&installed_crate_registry);
-        version_removed_crate.sort();
-        if !version_removed_crate.is_empty() {
-            for i in 0..(version_removed_crate.len() - 1) {
-                if version_removed_crate[i] == version_removed_crate[i + 1] {
-                    let old_crate_name = installed_crate_registry[i].to_string();
-                    old_crate_registry.push(old_crate_name);
-                }
-            }
-        }
-        old_crate_registry.sort();
-        old_crate_registry.dedup();
-
-        // list old git crate
-        let mut old_crate_git = Vec::new();
-        for crate_name in &installed_crate_git {
-            if !crate_name.contains("-HEAD") {
-                let name = crate_name.rsplitn(2, '-').collect::<Vec<&str>>();
-                let mut full_name_list = Vec::new();
-                for crates in fs::read_dir(db_dir).expect("failed to read db dir") {
-                    let entry = crates.unwrap().path();
-                    let path = entry.as_path();
-                    let file_name = path
-                        .file_name()
-                        .expect("failed to get file name form db directory sub folder");
-                    let file_name = file_name.to_str().unwrap().to_string();
-                    if file_name.contains(name[1]) {
-                        let output = std::process::Command::new("git")
-                            .arg("log")
-                            .arg("--pretty=format:'%h'")
-                            .arg("--max-count=1")
-                            .current_dir(path)
-                            .output()
-                            .expect("failed to execute process");
-                        let mut rev_value = std::str::from_utf8(&output.stdout)
-                            .expect("stdout is not utf8")
-                            .to_string();
-                        rev_value.retain(|c| c != '\'');
-                        let full_name = format!("{}-{}", name[1], rev_value);
-                        full_name_list.push(full_name)
-                    }
-                }
-                if !full_name_list.contains(crate_name) {
-                    old_crate_git.push(crate_name.to_string());
-                }
-            }
-        }
-        old_crate_git.sort();
-        old_crate_git.dedup();
+            get_installed_crate_registry(src_dir, cache_dir, crate_detail, errors);
+        let installed_crate_git =
+            get_installed_crate_git(checkout_dir, db_dir, crate_detail, errors);
+
+        // list latest git crate i.e checkout of latest rev of its git db
+        let db_list = if db_dir.exists() {
+            list_entries(db_dir, errors)
+        } else {
+            Vec::new()
+        };
+        let (latest_crate_git, mut unresolved_crate_git) =
+            latest_git(&db_list, &installed_crate_git, errors);
+
+        // list old crates which are not kept by default keep policy
+        let keep_policy = KeepPolicy::default();
+        let old_crate_registry = old_registry(&installed_crate_registry, keep_policy);
+        let old_crate_git = old_git(
+            &installed_crate_git,
+            &latest_crate_git,
+            &unresolved_crate_git,
+            crate_detail,
+            keep_policy,
+        );
 
         // list all used crates in rust program
-        let mut used_crate_registry = Vec::new();
-        let mut used_crate_git = Vec::new();
-        let mut cargo_toml_location = CargoTomlLocation::new();
+        let mut registry_list = installed_crate_registry
+            .iter()
+            .map(CrateId::registry)
+            .collect::<Vec<_>>();
+        registry_list.sort_unstable();
+        registry_list.dedup();
         let mut env_directory = env_list("TRIM_DIRECTORY");
-        let mut config_directory = config_file.directory().to_owned();
+        let mut config_directory = config_file.directory().clone();
         env_directory.append(&mut config_directory);
         env_directory.sort();
         env_directory.dedup();
-        for path in &env_directory {
-            let list_cargo_toml = list_cargo_toml(&Path::new(path));
-            let (mut registry_crate, mut git_crate) =
-                read_content(&list_cargo_toml.location_path(), db_dir);
-            cargo_toml_location.append(list_cargo_toml);
-            used_crate_registry.append(&mut registry_crate);
-            used_crate_git.append(&mut git_crate);
-        }
-        used_crate_registry.sort();
-        used_crate_registry.dedup();
-        used_crate_git.sort();
-        used_crate_registry.dedup();
+        let mut warnings = Vec::new();
+        let mut cargo_toml_location = scanner.scan(&env_directory, errors);
+        cargo_toml_location.set_workspaces(resolve_workspaces(
+            cargo_toml_location.location_path(),
+            errors,
+        ));
+        let (registry_workspace, git_workspace, lock_files) = read_content(
+            cargo_toml_location.workspaces(),
+            &registry_list,
+            &db_list,
+            &installed_crate_git,
+            &mut unresolved_crate_git,
+            (errors, &mut warnings),
+        );
+        let used_crate_registry = registry_workspace.keys().cloned().collect::<Vec<_>>();
+        let used_crate_git = git_workspace.keys().cloned().collect::<Vec<_>>();
+        unresolved_crate_git.sort();
+        unresolved_crate_git.dedup();
 
         // list orphan crates
-        let mut orphan_crate_registry = Vec::new();
-        let mut orphan_crate_git = Vec::new();
-        for crates in &installed_crate_registry {
-            if !used_crate_registry.contains(crates) {
-                orphan_crate_registry.push(crates.to_string());
-            }
-        }
-        for crates in &installed_crate_git {
-            if crates.contains("-HEAD") {
-                let split_installed = crates.rsplitn(2, '-').collect::<Vec<&str>>();
-                if used_crate_git.is_empty() {
-                    orphan_crate_git.push(crates.to_string());
-                }
-                let mut used_in_project = false;
-                for used in &used_crate_git {
-                    if used.contains(split_installed[1]) {
-                        used_in_project = true;
-                        // Break if found to be used one time no need to check for other
-                        break;
-                    }
-                }
-                if !used_in_project {
-                    orphan_crate_git.push(crates.to_string());
-                }
-            } else if !used_crate_git.contains(crates) {
-                orphan_crate_git.push(crates.to_string());
-            }
-        }
-        orphan_crate_registry.sort();
-        orphan_crate_registry.dedup();
-        orphan_crate_git.sort();
-        orphan_crate_git.dedup();
+        let orphan_crate_registry =
+            orphan_registry(&installed_crate_registry, &used_crate_registry);
+        let orphan_crate_git =
+            orphan_git(&installed_crate_git, &used_crate_git, &unresolved_crate_git);
 
         Self {
             installed_bin,
@@ -208,220 +269,483 @@ impl CrateList {
             installed_crate_git,
             old_crate_registry,
             old_crate_git,
+            latest_crate_git,
+            unresolved_crate_git,
             used_crate_registry,
             used_crate_git,
             orphan_crate_registry,
             orphan_crate_git,
+            registry_workspace,
+            git_workspace,
             cargo_toml_location,
+            project_directory: env_directory.iter().map(PathBuf::from).collect(),
+            lock_files,
+            warnings,
         }
     }
 
-    // provide list of installed bin
-    pub(crate) fn installed_bin(&self) -> &Vec<String> {
+    /// list old registry and git crates again using provided keep policy
+    /// instead of default policy which keeps only newest version of crate
+    pub fn set_keep_policy(&mut self, keep_policy: KeepPolicy, crate_detail: &CrateDetail) {
+        self.old_crate_registry = old_registry(&self.installed_crate_registry, keep_policy);
+        self.old_crate_git = old_git(
+            &self.installed_crate_git,
+            &self.latest_crate_git,
+            &self.unresolved_crate_git,
+            crate_detail,
+            keep_policy,
+        );
+    }
+
+    /// keep only registry crates of registry directory for which filter returns
+    /// true, git crates are removed from list since they don't belong to any
+    /// registry
+    pub fn filter_registry<F>(&mut self, filter: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        for list in [
+            &mut self.installed_crate_registry,
+            &mut self.old_crate_registry,
+            &mut self.used_crate_registry,
+            &mut self.orphan_crate_registry,
+        ] {
+            list.retain(|crate_id| filter(crate_id.registry()));
+        }
+        self.registry_workspace
+            .retain(|crate_id, _| filter(crate_id.registry()));
+        self.git_workspace.clear();
+        for list in [
+            &mut self.installed_crate_git,
+            &mut self.old_crate_git,
+            &mut self.latest_crate_git,
+            &mut self.used_crate_git,
+            &mut self.orphan_crate_git,
+        ] {
+            list.clear();
+        }
+        self.unresolved_crate_git.clear();
+    }
+
+    /// provide list of installed bin
+    #[must_use]
+    pub fn installed_bin(&self) -> &Vec<String> {
         &self.installed_bin
     }
 
-    // provide list of installed registry
-    pub(crate) fn installed_registry(&self) -> &Vec<String> {
+    /// provide list of installed registry
+    #[must_use]
+    pub fn installed_registry(&self) -> &Vec<CrateId> {
         &self.installed_crate_registry
     }
 
-    // provide list of old registry
-    pub(crate) fn old_registry(&self) -> &Vec<String> {
+    /// provide list of old registry
+    #[must_use]
+    pub fn old_registry(&self) -> &Vec<CrateId> {
         &self.old_crate_registry
     }
 
-    // provide list of used registry
-    pub(crate) fn used_registry(&self) -> &Vec<String> {
+    /// provide list of used registry
+    #[must_use]
+    pub fn used_registry(&self) -> &Vec<CrateId> {
         &self.used_crate_registry
     }
 
-    // provide list o orphan registry
-    pub(crate) fn orphan_registry(&self) -> &Vec<String> {
+    /// provide list o orphan registry
+    #[must_use]
+    pub fn orphan_registry(&self) -> &Vec<CrateId> {
         &self.orphan_crate_registry
     }
 
-    // provide list of installed git
-    pub(crate) fn installed_git(&self) -> &Vec<String> {
+    /// provide list of installed git
+    #[must_use]
+    pub fn installed_git(&self) -> &Vec<GitId> {
         &self.installed_crate_git
     }
 
-    // provide list of old git
-    pub(crate) fn old_git(&self) -> &Vec<String> {
+    /// provide list of old git
+    #[must_use]
+    pub fn old_git(&self) -> &Vec<GitId> {
         &self.old_crate_git
     }
 
-    // provide list of used git
-    pub(crate) fn used_git(&self) -> &Vec<String> {
+    /// provide list of used git
+    #[must_use]
+    pub fn used_git(&self) -> &Vec<GitId> {
         &self.used_crate_git
     }
 
-    // provide list of orphan git
-    pub(crate) fn orphan_git(&self) -> &Vec<String> {
+    /// provide list of orphan git
+    #[must_use]
+    pub fn orphan_git(&self) -> &Vec<GitId> {
         &self.orphan_crate_git
     }
 
-    // list out path of directory which contains cargo lock file
-    pub(crate) fn cargo_toml_location(&self) -> &CargoTomlLocation {
+    /// provide root of workspaces whose Cargo.lock uses registry crate
+    pub fn registry_workspaces(&self, crate_id: &CrateId) -> &[PathBuf] {
+        self.registry_workspace
+            .get(crate_id)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// provide root of workspaces whose Cargo.lock uses git crate
+    pub fn git_workspaces(&self, git_id: &GitId) -> &[PathBuf] {
+        self.git_workspace.get(git_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// list out installed registry crates with provided name, only crate of
+    /// provided version is listed when version is present
+    #[must_use]
+    pub fn installed_registry_named(&self, name: &str, version: Option<&Version>) -> Vec<&CrateId> {
+        self.installed_crate_registry
+            .iter()
+            .filter(|crate_id| {
+                crate_id.name() == name
+                    && version.is_none_or(|version| crate_id.version() == version)
+            })
+            .collect()
+    }
+
+    /// list out installed git db and checkout whose repo name is provided name
+    #[must_use]
+    pub fn installed_git_named(&self, name: &str) -> Vec<&GitId> {
+        self.installed_crate_git
+            .iter()
+            .filter(|git_id| git_id.name() == name)
+            .collect()
+    }
+
+    /// list out registry crates whose source and archive are not used for more
+    /// than max age
+    #[must_use]
+    pub fn stale_registry(&self, crate_detail: &CrateDetail, max_age: Duration) -> Vec<CrateId> {
+        let last_used = crate_detail.registry_crates_last_used();
+        self.installed_crate_registry
+            .iter()
+            .filter(|crate_id| is_stale(last_used.get(*crate_id), max_age))
+            .cloned()
+            .collect()
+    }
+
+    /// list out git crates whose checkout is not used for more than max age, git
+    /// db is never considered stale
+    #[must_use]
+    pub fn stale_git(&self, crate_detail: &CrateDetail, max_age: Duration) -> Vec<GitId> {
+        let last_used = crate_detail.git_crates_last_used();
+        self.installed_crate_git
+            .iter()
+            .filter(|git_id| is_stale(last_used.get(*git_id), max_age))
+            .cloned()
+            .collect()
+    }
+
+    /// list out path of directory which contains cargo lock file
+    #[must_use]
+    pub fn cargo_toml_location(&self) -> &CargoTomlLocation {
         &self.cargo_toml_location
     }
-}
 
-// remove version tag from crates full tag mini function of remove_version
-// function
-fn clear_version_value(a: &str) -> String {
-    let list = a.rsplitn(2, '-');
-    let mut value = String::new();
-    for (i, val) in list.enumerate() {
-        if i == 1 {
-            value = val.to_string()
+    /// list size of registry and git crates used by each project directory
+    /// along with size of crates used only by that project directory
+    #[must_use]
+    pub fn project_usage(&self, crate_detail: &CrateDetail) -> Vec<ProjectUsage> {
+        // project directory containing workspace, nested project directories
+        // both contain workspace
+        let workspace_projects = self
+            .cargo_toml_location
+            .workspaces()
+            .iter()
+            .map(|workspace| {
+                let projects = self
+                    .project_directory
+                    .iter()
+                    .filter(|directory| {
+                        workspace.root().starts_with(directory)
+                            || workspace
+                                .members()
+                                .iter()
+                                .any(|member| member.starts_with(directory))
+                    })
+                    .collect::<BTreeSet<_>>();
+                (workspace.root(), projects)
+            })
+            .collect::<HashMap<_, _>>();
+        let projects_of = |roots: &[PathBuf]| {
+            roots
+                .iter()
+                .filter_map(|root| workspace_projects.get(root))
+                .flatten()
+                .copied()
+                .collect::<BTreeSet<_>>()
+        };
+        let mut usage = self
+            .project_directory
+            .iter()
+            .map(|directory| ProjectUsage {
+                directory: directory.clone(),
+                registry_size: 0,
+                registry_exclusive_size: 0,
+                git_size: 0,
+                git_exclusive_size: 0,
+            })
+            .collect::<Vec<_>>();
+        for (crate_id, roots) in &self.registry_workspace {
+            let size = crate_detail
+                .registry_crates_archive()
+                .get(crate_id)
+                .copied()
+                .unwrap_or(0)
+                + crate_detail
+                    .registry_crates_source()
+                    .get(crate_id)
+                    .copied()
+                    .unwrap_or(0);
+            let projects = projects_of(roots);
+            for project in usage
+                .iter_mut()
+                .filter(|project| projects.contains(&project.directory))
+            {
+                project.registry_size += size;
+                if projects.len() == 1 {
+                    project.registry_exclusive_size += size;
+                }
+            }
+        }
+        for (git_id, roots) in &self.git_workspace {
+            let size = crate_detail
+                .git_crates_archive()
+                .get(git_id)
+                .copied()
+                .unwrap_or(0)
+                + crate_detail
+                    .git_crates_source()
+                    .get(git_id)
+                    .copied()
+                    .unwrap_or(0);
+            let projects = projects_of(roots);
+            for project in usage
+                .iter_mut()
+                .filter(|project| projects.contains(&project.directory))
+            {
+                project.git_size += size;
+                if projects.len() == 1 {
+                    project.git_exclusive_size += size;
+                }
+            }
         }
+        usage
+    }
+
+    /// provide Cargo.lock file of all workspaces which are read while listing
+    #[must_use]
+    pub fn lock_files(&self) -> &[LockFile] {
+        &self.lock_files
+    }
+
+    /// provide problem found while reading Cargo.lock file, lock file with
+    /// problem is skipped fully or partially but it doesn't stop listing
+    #[must_use]
+    pub fn warnings(&self) -> &[TrimError] {
+        &self.warnings
     }
-    value
 }
 
-// List out cargo.toml file present directory inside directory listed inside
-// config file
-fn list_cargo_toml(path: &Path) -> CargoTomlLocation {
-    let mut list = CargoTomlLocation::new();
-    if path.exists() {
-        for entry in std::fs::read_dir(path)
-            .expect("failed to read directory while trying to find cargo.toml")
-        {
-            let data_path_buf = entry.unwrap().path();
-            let data = data_path_buf.as_path();
-            if data.is_dir() {
-                let kids_list = list_cargo_toml(data);
-                list.append(kids_list);
+// Read out content of Cargo.lock file of each workspace to list out crates
+// present along with root of workspaces using them so can be used for orphan
+// clean, lock file which is read is also returned
+fn read_content(
+    list: &[Workspace],
+    registry_list: &[&str],
+    db_list: &[PathBuf],
+    installed_crate_git: &[GitId],
+    unresolved_crate_git: &mut Vec<String>,
+    (errors, warnings): (&mut Vec<TrimError>, &mut Vec<TrimError>),
+) -> (UsedBy<CrateId>, UsedBy<GitId>, Vec<LockFile>) {
+    let mut present_crate_registry = BTreeMap::new();
+    let mut present_crate_git = BTreeMap::new();
+    let mut lock_files = Vec::new();
+    for workspace in list {
+        let lock_file = workspace.lock_file();
+        if !lock_file.exists() {
+            continue;
+        }
+        // lock file which cannot be read only skips its workspace
+        let lock_file = match LockFile::read(&lock_file, warnings) {
+            Ok(lock_file) => lock_file,
+            Err(e) => {
+                warnings.push(e);
+                continue;
+            }
+        };
+        for package in lock_file.packages() {
+            let Some(source) = package.source() else {
+                continue;
+            };
+            let name = package.name();
+            if source.starts_with("registry+") || source.starts_with("sparse+") {
+                let Ok(version) = Version::parse(package.version()) else {
+                    warnings.push(TrimError::LockFile {
+                        path: lock_file.path().clone(),
+                        message: format!(
+                            "package {name} has invalid version {}",
+                            package.version()
+                        ),
+                    });
+                    continue;
+                };
+                // registry directory name is made up of host and hash of index
+                // url so crate is used from registry whose name matches. When
+                // hash is not known crate is used from all registry with same
+                // host and host itself is used when registry is not present
+                let host = url_host(source).unwrap_or_default();
+                let dir_names = registry_dir_names(source);
+                let mut registries = registry_list
+                    .iter()
+                    .filter(|registry| dir_names.iter().any(|name| name == *registry))
+                    .collect::<Vec<_>>();
+                if registries.is_empty() {
+                    registries = registry_list
+                        .iter()
+                        .filter(|registry| registry_host(registry) == host)
+                        .collect();
+                }
+                if registries.is_empty() {
+                    add_workspace(
+                        &mut present_crate_registry,
+                        CrateId::new(name, version.clone(), host),
+                        workspace,
+                    );
+                }
+                for registry in registries {
+                    add_workspace(
+                        &mut present_crate_registry,
+                        CrateId::new(name, version.clone(), registry),
+                        workspace,
+                    );
+                }
             }
-            if data.is_file() && data.ends_with("Cargo.toml") {
-                list.add_path(path.to_path_buf());
+            if let Some(git_source) = source.strip_prefix("git+") {
+                for git_id in used_git(
+                    git_source,
+                    db_list,
+                    installed_crate_git,
+                    unresolved_crate_git,
+                    errors,
+                ) {
+                    add_workspace(&mut present_crate_git, git_id, workspace);
+                }
             }
         }
+        lock_files.push(lock_file);
     }
-    list
+    (present_crate_registry, present_crate_git, lock_files)
 }
 
-// Read out content of cargo.lock file to list out crates present so can be used
-// for orphan clean
-fn read_content(list: &[PathBuf], db_dir: &Path) -> (Vec<String>, Vec<String>) {
-    let mut present_crate_registry = Vec::new();
-    let mut present_crate_git = Vec::new();
-    for lock in list.iter() {
-        let mut lock_folder = lock.clone();
-        lock_folder.push("Cargo.lock");
-        if lock_folder.exists() {
-            let lock_file = lock_folder
-                .to_str()
-                .expect("Failed to convert lock_folder to str");
-            let mut buffer = String::new();
-            let mut file = std::fs::File::open(lock_file).expect("failed to open cargo lock file");
-            file.read_to_string(&mut buffer)
-                .expect("failed to read cargo lock content to string");
-            let cargo_lock_data: LockData =
-                toml::from_str(&buffer).expect("Failed to convert to Toml format");
-            if let Some(packages) = cargo_lock_data.package() {
-                for package in packages {
-                    if let Some(source) = package.source() {
-                        let name = package.name();
-                        let version = package.version();
-                        if source.contains("registry+") {
-                            let full_name = format!("{}-{}", name, version);
-                            present_crate_registry.push(full_name);
-                        }
-                        if source.contains("git+") {
-                            let mut path_db_list = Vec::new();
-                            for git_db in fs::read_dir(db_dir).expect("failed to read git db dir") {
-                                let entry = git_db.unwrap().path();
-                                let file_name = entry
-                                    .file_name()
-                                    .expect("failed to get file name")
-                                    .to_str()
-                                    .expect("failed to convert OS str to str");
-                                if file_name.contains(name) {
-                                    let mut path_db = db_dir.to_path_buf();
-                                    path_db.push(&file_name);
-                                    path_db_list.push(path_db);
-                                }
-                            }
-                            for path_db in path_db_list {
-                                if source.contains("?rev=") {
-                                    let rev: Vec<&str> = source.split("?rev=").collect();
-                                    let rev_sha: Vec<&str> = rev[1].split('#').collect();
-                                    let rev_value = rev_sha[1].to_string();
-                                    let rev_short_form = &rev_value[..=6];
-                                    let full_name = format!("{}-{}", name, rev_short_form);
-                                    present_crate_git.push(full_name);
-                                } else if source.contains("?branch=") || source.contains("?tag=") {
-                                    let branch: Vec<&str> = if source.contains("?branch=") {
-                                        source.split("?branch=").collect()
-                                    } else {
-                                        source.split("?tag=").collect()
-                                    };
-                                    let branch: Vec<&str> = branch[1].split('#').collect();
-                                    let branch_value = branch[0];
-                                    let output = std::process::Command::new("git")
-                                        .arg("log")
-                                        .arg("--pretty=format:%h")
-                                        .arg("--max-count=1")
-                                        .arg(branch_value)
-                                        .current_dir(path_db)
-                                        .output()
-                                        .expect(
-                                            "failed to execute command for pretty log of branch",
-                                        );
-                                    let rev_value = std::str::from_utf8(&output.stdout)
-                                        .expect("stdout is not utf8");
-                                    let full_name = format!("{}-{}", name, rev_value);
-                                    present_crate_git.push(full_name);
-                                } else {
-                                    let output = std::process::Command::new("git")
-                                        .arg("log")
-                                        .arg("--pretty=format:%h")
-                                        .arg("--max-count=1")
-                                        .current_dir(path_db)
-                                        .output()
-                                        .expect("failed to process command");
-                                    let rev_value = std::str::from_utf8(&output.stdout)
-                                        .expect("stdout is not ut8");
-                                    let full_name = format!("{}-{}", name, rev_value);
-                                    present_crate_git.push(full_name);
-                                }
-                            }
-                        }
-                    }
-                }
+// record root of workspace which uses crate
+fn add_workspace<T: Ord>(present: &mut UsedBy<T>, id: T, workspace: &Workspace) {
+    let roots = present.entry(id).or_default();
+    if !roots.contains(workspace.root()) {
+        roots.push(workspace.root().clone());
+    }
+}
+
+// list out git db and checkout used by git source of Cargo.lock. Source is in
+// format of url?query#commit where git db is matched by ident of url i.e its
+// last path segment and then by presence of commit inside db, checkout is
+// matched by prefix of full commit id
+fn used_git(
+    git_source: &str,
+    db_list: &[PathBuf],
+    installed_crate_git: &[GitId],
+    unresolved_crate_git: &mut Vec<String>,
+    errors: &mut Vec<TrimError>,
+) -> Vec<GitId> {
+    let (url, commit) = match git_source.split_once('#') {
+        Some((url, commit)) => (url, Some(commit)),
+        None => (git_source, None),
+    };
+    let (url, query) = url.split_once('?').unwrap_or((url, ""));
+    let ident = git_ident(url);
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+    for path_db in db_list {
+        let db = file_name(path_db);
+        if !db
+            .rsplit_once('-')
+            .is_some_and(|(name, _)| name.eq_ignore_ascii_case(ident))
+        {
+            continue;
+        }
+        // Cargo.lock without commit only have branch, tag or rev in query
+        let rev = match commit {
+            Some(commit) => Ok(commit.to_string()),
+            None => match query.split_once('=') {
+                Some(("rev", rev)) => Ok(rev.to_string()),
+                Some(("branch" | "tag", branch)) => latest_rev(path_db, Some(branch)),
+                _ => latest_rev(path_db, None),
+            },
+        };
+        match rev.and_then(|rev| Ok((find_commit(path_db, &rev)?, rev))) {
+            Ok((Some(full_commit), _)) => found.push((db, full_commit)),
+            Ok((None, rev)) => missing.push((db, rev)),
+            Err(e) => {
+                errors.push(e);
+                unresolved_crate_git.push(db);
             }
         }
     }
-    (present_crate_registry, present_crate_git)
+    // commit which is not present in any db is still matched with all db of
+    // same ident since checkout can outlive its commit inside db
+    let matched = if found.is_empty() { missing } else { found };
+    let mut used = Vec::new();
+    for (db, commit) in matched {
+        used.push(GitId::new(&db, None));
+        let mut checkouts = installed_crate_git
+            .iter()
+            .filter(|git_id| git_id.db() == db && git_id.is_checkout_of(&commit))
+            .cloned()
+            .collect::<Vec<_>>();
+        if checkouts.is_empty() {
+            used.push(GitId::new(&db, Some(&commit)));
+        }
+        used.append(&mut checkouts);
+    }
+    used
 }
 
-// Function used to remove version from installed_crate_registry list so can be
-// used for old clean flag
-fn remove_version(installed_crate_registry: &[String]) -> Vec<String> {
-    let mut removed_version = Vec::new();
-    for i in installed_crate_registry.iter() {
-        let data = clear_version_value(i);
-        removed_version.push(data);
+// get ident of git url used by cargo as name of git db and checkout directory,
+// it is last path segment of url without .git suffix
+fn git_ident(url: &str) -> &str {
+    let ident = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".git");
+    if ident.is_empty() {
+        "_empty"
+    } else {
+        ident
     }
-    removed_version
 }
 
 // list out installed bin
-fn get_installed_bin(bin_dir: &Path, crate_detail: &mut CrateDetail) -> Vec<String> {
+fn get_installed_bin(
+    bin_dir: &Path,
+    crate_detail: &mut CrateDetail,
+    errors: &mut Vec<TrimError>,
+) -> Vec<String> {
     let mut installed_bin = Vec::new();
     if bin_dir.exists() {
-        for entry in fs::read_dir(bin_dir).expect("failed to read bin directory") {
-            let entry = entry.unwrap().path();
-            let path = entry.as_path();
-            let bin_size = get_size(&path).expect("failed to get size of bin directory");
-            let file_name = path
-                .file_name()
-                .expect("failed to get file name from bin directory");
-            let bin_name = file_name.to_str().unwrap().to_string();
-            crate_detail.add_bin(bin_name.to_owned(), bin_size);
-            installed_bin.push(bin_name)
+        for path in list_entries(bin_dir, errors) {
+            match get_size(&path) {
+                Ok(bin_size) => {
+                    let bin_name = file_name(&path);
+                    crate_detail.add_bin(bin_name.clone(), bin_size);
+                    installed_bin.push(bin_name);
+                }
+                Err(e) => errors.push(e),
+            }
         }
     }
     installed_bin.sort();
@@ -433,38 +757,51 @@ fn get_installed_crate_registry(
     src_dir: &Path,
     cache_dir: &Path,
     crate_detail: &mut CrateDetail,
-) -> Vec<String> {
+    errors: &mut Vec<TrimError>,
+) -> Vec<CrateId> {
     let mut installed_crate_registry = Vec::new();
     if src_dir.exists() {
-        for entry in fs::read_dir(src_dir).expect("failed to read src directory") {
-            let registry = entry.unwrap().path();
-            for entry in fs::read_dir(registry).expect("failed to read registry folder") {
-                let entry = entry.unwrap().path();
-                let path = entry.as_path();
-                let crate_size = get_size(&path).expect("failed to get registry crate size");
-                let file_name = path
-                    .file_name()
-                    .expect("failed to get file name form main entry");
-                let crate_name = file_name.to_str().unwrap().to_string();
-                crate_detail.add_registry_crate_source(crate_name.to_owned(), crate_size);
-                installed_crate_registry.push(crate_name)
+        for registry in list_entries(src_dir, errors) {
+            let registry_name = file_name(&registry);
+            for path in list_entries(&registry, errors) {
+                match get_size(&path) {
+                    Ok(crate_size) => {
+                        let Some(crate_id) = CrateId::parse(&file_name(&path), &registry_name)
+                        else {
+                            continue;
+                        };
+                        if let Some(time) = last_used(&path) {
+                            crate_detail.add_registry_crate_last_used(crate_id.clone(), time);
+                        }
+                        crate_detail.add_registry_crate_source(crate_id.clone(), crate_size);
+                        installed_crate_registry.push(crate_id);
+                    }
+                    Err(e) => errors.push(e),
+                }
             }
         }
     }
     if cache_dir.exists() {
-        for entry in fs::read_dir(cache_dir).expect("failed to read cache dir") {
-            let registry = entry.unwrap().path();
-            for entry in fs::read_dir(registry).expect("failed to read cache dir registry folder") {
-                let entry = entry.unwrap().path();
-                let path = entry.as_path();
-                let file_name = path
-                    .file_name()
-                    .expect("failed to get file name from cache dir");
-                let crate_size = get_size(&path).expect("failed to get size");
-                let crate_name = file_name.to_str().unwrap().to_string();
-                let splitted_name = crate_name.rsplitn(2, '.').collect::<Vec<&str>>();
-                crate_detail.add_registry_crate_archive(splitted_name[1].to_owned(), crate_size);
-                installed_crate_registry.push(splitted_name[1].to_owned());
+        for registry in list_entries(cache_dir, errors) {
+            let registry_name = file_name(&registry);
+            for path in list_entries(&registry, errors) {
+                match get_size(&path) {
+                    Ok(crate_size) => {
+                        let file_name = file_name(&path);
+                        let Some(crate_id) = file_name
+                            .strip_suffix(".crate")
+                            .and_then(|full_name| CrateId::parse(full_name, &registry_name))
+                        else {
+                            continue;
+                        };
+                        if let Some(time) = last_used(&path) {
+                            crate_detail.add_registry_crate_last_used(crate_id.clone(), time);
+                        }
+                        crate_detail.add_registry_crate_archive(crate_id.clone(), crate_size);
+                        installed_crate_registry.push(crate_id);
+                    }
+                    Err(e) => errors.push(e),
+                }
             }
         }
     }
@@ -478,41 +815,37 @@ fn get_installed_crate_git(
     checkout_dir: &Path,
     db_dir: &Path,
     crate_detail: &mut CrateDetail,
-) -> Vec<String> {
+    errors: &mut Vec<TrimError>,
+) -> Vec<GitId> {
     let mut installed_crate_git = Vec::new();
     if checkout_dir.exists() {
-        for entry in fs::read_dir(checkout_dir).expect("failed to read checkout directory") {
-            let entry = entry.unwrap().path();
-            let path = entry.as_path();
-            let file_path = path
-                .file_name()
-                .expect("failed to obtain checkout directory sub folder file name");
-            for git_sha_entry in fs::read_dir(path).expect("failed to read checkout dir sub folder")
-            {
-                let git_sha_entry = git_sha_entry.unwrap().path();
-                let git_sha_path = git_sha_entry.as_path();
-                let crate_size = get_size(git_sha_path).expect("failed to get folder size");
-                let git_sha_file_name = git_sha_path.file_name().expect("failed to get file name");
-                let git_sha = git_sha_file_name.to_str().unwrap().to_string();
-                let file_name = file_path.to_str().unwrap().to_string();
-                let splitted_name = file_name.rsplitn(2, '-').collect::<Vec<&str>>();
-                let full_name = format!("{}-{}", splitted_name[1], git_sha);
-                crate_detail.add_git_crate_archive(full_name.to_owned(), crate_size);
-                installed_crate_git.push(full_name)
+        for path in list_entries(checkout_dir, errors) {
+            let db = file_name(&path);
+            for git_sha_path in list_entries(&path, errors) {
+                match get_size(&git_sha_path) {
+                    Ok(crate_size) => {
+                        let git_id = GitId::new(&db, Some(&file_name(&git_sha_path)));
+                        if let Some(time) = last_used(&git_sha_path) {
+                            crate_detail.add_git_crate_last_used(git_id.clone(), time);
+                        }
+                        crate_detail.add_git_crate_archive(git_id.clone(), crate_size);
+                        installed_crate_git.push(git_id);
+                    }
+                    Err(e) => errors.push(e),
+                }
             }
         }
     }
     if db_dir.exists() {
-        for entry in fs::read_dir(db_dir).expect("failed to read db dir") {
-            let entry = entry.unwrap().path();
-            let path = entry.as_path();
-            let crate_size = get_size(path).expect("failed to get size of db dir folders");
-            let file_name = path.file_name().expect("failed to get file name");
-            let file_name = file_name.to_str().unwrap().to_string();
-            let splitted_name = file_name.rsplitn(2, '-').collect::<Vec<&str>>();
-            let full_name = format!("{}-HEAD", splitted_name[1]);
-            crate_detail.add_git_crate_source(full_name.to_owned(), crate_size);
-            installed_crate_git.push(full_name);
+        for path in list_entries(db_dir, errors) {
+            match get_size(&path) {
+                Ok(crate_size) => {
+                    let git_id = GitId::new(&file_name(&path), None);
+                    crate_detail.add_git_crate_source(git_id.clone(), crate_size);
+                    installed_crate_git.push(git_id);
+                }
+                Err(e) => errors.push(e),
+            }
         }
     }
     installed_crate_git.sort();
@@ -520,6 +853,175 @@ fn get_installed_crate_git(
     installed_crate_git
 }
 
+// list latest git crate i.e checkout of latest rev of its git db along with git
+// db whose rev cannot be resolved, such db is neither listed as old nor orphan
+fn latest_git(
+    db_list: &[PathBuf],
+    installed_crate_git: &[GitId],
+    errors: &mut Vec<TrimError>,
+) -> (Vec<GitId>, Vec<String>) {
+    let mut latest_crate_git = Vec::new();
+    let mut unresolved_crate_git = Vec::new();
+    for path in db_list {
+        let db = file_name(path);
+        let checkouts = installed_crate_git
+            .iter()
+            .filter(|git_id| git_id.db() == db && git_id.rev().is_some())
+            .collect::<Vec<_>>();
+        if checkouts.is_empty() {
+            continue;
+        }
+        match latest_rev(path, None) {
+            Ok(commit) => latest_crate_git.extend(
+                checkouts
+                    .into_iter()
+                    .filter(|git_id| git_id.is_checkout_of(&commit))
+                    .cloned(),
+            ),
+            Err(e) => {
+                errors.push(e);
+                unresolved_crate_git.push(db);
+            }
+        }
+    }
+    latest_crate_git.sort();
+    latest_crate_git.dedup();
+    (latest_crate_git, unresolved_crate_git)
+}
+
+// list orphan registry crate i.e crate which is not used by any lock file
+fn orphan_registry(
+    installed_crate_registry: &[CrateId],
+    used_crate_registry: &[CrateId],
+) -> Vec<CrateId> {
+    let mut orphan_crate_registry = installed_crate_registry
+        .iter()
+        .filter(|crate_id| !used_crate_registry.contains(crate_id))
+        .cloned()
+        .collect::<Vec<_>>();
+    orphan_crate_registry.sort();
+    orphan_crate_registry.dedup();
+    orphan_crate_registry
+}
+
+// list orphan git crate i.e checkout which is not used by any lock file and git
+// db whose none of checkout is used. Git db whose rev cannot be resolved is
+// never orphan
+fn orphan_git(
+    installed_crate_git: &[GitId],
+    used_crate_git: &[GitId],
+    unresolved_crate_git: &[String],
+) -> Vec<GitId> {
+    let mut orphan_crate_git = Vec::new();
+    for git_id in installed_crate_git {
+        if unresolved_crate_git.iter().any(|db| db == git_id.db()) {
+            continue;
+        }
+        // git db is used as long as any checkout of it is used
+        let used = if git_id.rev().is_none() {
+            used_crate_git.iter().any(|used| used.db() == git_id.db())
+        } else {
+            used_crate_git.contains(git_id)
+        };
+        if !used {
+            orphan_crate_git.push(git_id.clone());
+        }
+    }
+    orphan_crate_git.sort();
+    orphan_crate_git.dedup();
+    orphan_crate_git
+}
+
+// list old registry crate i.e crate which is not one of newest versions of
+// crate with same name from same registry or same semver compatible range when
+// kept per major
+fn old_registry(installed_crate_registry: &[CrateId], keep_policy: KeepPolicy) -> Vec<CrateId> {
+    let mut versions = HashMap::new();
+    for crate_id in installed_crate_registry {
+        let range = keep_policy
+            .per_major()
+            .then(|| compatible_range(crate_id.version()));
+        versions
+            .entry((crate_id.registry(), crate_id.name(), range))
+            .or_insert_with(Vec::new)
+            .push(crate_id);
+    }
+    let mut old_crate_registry = Vec::new();
+    for mut crate_ids in versions.into_values() {
+        crate_ids.sort_by(|a, b| b.version().cmp(a.version()));
+        old_crate_registry.extend(crate_ids.into_iter().skip(keep_policy.count()).cloned());
+    }
+    old_crate_registry.sort();
+    old_crate_registry
+}
+
+// list old git crate i.e checkout which is not of latest rev of its git db and
+// not one of most recently used checkout of same db. Git checkout doesn't
+// have version so it is only grouped by db even when kept per major
+fn old_git(
+    installed_crate_git: &[GitId],
+    latest_crate_git: &[GitId],
+    unresolved_crate_git: &[String],
+    crate_detail: &CrateDetail,
+    keep_policy: KeepPolicy,
+) -> Vec<GitId> {
+    let mut checkouts = HashMap::new();
+    for git_id in installed_crate_git {
+        if git_id.rev().is_none()
+            || latest_crate_git.contains(git_id)
+            || unresolved_crate_git.iter().any(|db| db == git_id.db())
+        {
+            continue;
+        }
+        checkouts
+            .entry(git_id.db())
+            .or_insert_with(Vec::new)
+            .push(git_id);
+    }
+    let last_used = crate_detail.git_crates_last_used();
+    let mut old_crate_git = Vec::new();
+    for mut git_ids in checkouts.into_values() {
+        git_ids.sort_by(|a, b| last_used.get(*b).cmp(&last_used.get(*a)).then(a.cmp(b)));
+        // latest rev of git db is newest version so it counts as one of kept
+        // checkout even when it is not checked out
+        let keep = keep_policy.count().saturating_sub(1);
+        old_crate_git.extend(git_ids.into_iter().skip(keep).cloned());
+    }
+    old_crate_git.sort();
+    old_crate_git
+}
+
+// semver compatible range of version, version in same range are compatible to
+// each other i.e 1.x.y, 0.3.y and 0.0.z
+fn compatible_range(version: &Version) -> (u64, u64, u64) {
+    if version.major > 0 {
+        (version.major, 0, 0)
+    } else if version.minor > 0 {
+        (0, version.minor, 0)
+    } else {
+        (0, 0, version.patch)
+    }
+}
+
+// list out entries of directory, directory which cannot be read is treated as
+// empty directory and its error is pushed to errors
+fn list_entries(path: &Path, errors: &mut Vec<TrimError>) -> Vec<PathBuf> {
+    read_dir(path).unwrap_or_else(|e| {
+        errors.push(e);
+        Vec::new()
+    })
+}
+
+// check if last used time is older than max age, crate without last used time
+// is never stale
+fn is_stale(last_used: Option<&SystemTime>, max_age: Duration) -> bool {
+    last_used.is_some_and(|time| {
+        SystemTime::now()
+            .duration_since(*time)
+            .is_ok_and(|age| age > max_age)
+    })
+}
+
 // list out a env variables list in vector form
 pub(crate) fn env_list(variable: &str) -> Vec<String> {
     let list = env::var(variable);
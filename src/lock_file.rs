@@ -0,0 +1,319 @@
+use crate::error::{io_error, TrimError};
+use serde_derive::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+// newest Cargo.lock version which is known to be read correctly
+const LATEST_LOCK_VERSION: u32 = 4;
+
+// prefix of key of v1 metadata table which stores checksum of package
+const CHECKSUM_PREFIX: &str = "checksum ";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LockData {
+    version: Option<u32>,
+    package: Vec<PackageData>,
+    // very old lock file stores root package in separate table
+    root: Option<PackageData>,
+    metadata: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PackageData {
+    name: Option<String>,
+    version: Option<String>,
+    source: Option<String>,
+    checksum: Option<String>,
+    dependencies: Vec<String>,
+}
+
+/// Dependency of package listed in Cargo.lock. Version and source are only
+/// present when they are needed to distinguish package with same name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockDependency {
+    name: String,
+    version: Option<String>,
+    source: Option<String>,
+}
+
+impl LockDependency {
+    // parse dependency in `name`, `name version` or `name version (source)`
+    // format
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, ' ');
+        let name = parts.next().filter(|name| !name.is_empty())?;
+        let version = parts.next().map(ToString::to_string);
+        let source = parts
+            .next()
+            .and_then(|source| source.strip_prefix('('))
+            .and_then(|source| source.strip_suffix(')'))
+            .map(ToString::to_string);
+        Some(Self {
+            name: name.to_string(),
+            version,
+            source,
+        })
+    }
+
+    /// return name of dependency
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// return version of dependency if present
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// return source of dependency if present
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// check if dependency refers to package
+    #[must_use]
+    pub fn matches(&self, package: &LockPackage) -> bool {
+        self.name == package.name
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|version| *version == package.version)
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| Some(source) == package.source.as_ref())
+    }
+}
+
+/// Package listed in Cargo.lock
+#[derive(Clone, Debug)]
+pub struct LockPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+    dependencies: Vec<LockDependency>,
+}
+
+impl LockPackage {
+    /// return name of package
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// return version of package
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// return source of package, package of workspace or path dependency
+    /// doesn't have source
+    #[must_use]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// return checksum of package, checksum of v1 lock file is read from its
+    /// metadata table
+    #[must_use]
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+
+    /// return dependencies of package
+    #[must_use]
+    pub fn dependencies(&self) -> &[LockDependency] {
+        &self.dependencies
+    }
+}
+
+/// Cargo.lock file of workspace. Lock file of version 1 to 4 is supported,
+/// version 1 and 2 are detected from content since they don't have version
+/// header
+pub struct LockFile {
+    path: PathBuf,
+    version: u32,
+    packages: Vec<LockPackage>,
+}
+
+impl LockFile {
+    /// read and parse Cargo.lock file, problem which doesn't prevent reading
+    /// rest of file such as package without version or unknown lock file
+    /// version is pushed to warnings
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if lock file cannot be read or parsed
+    pub fn read(path: &Path, warnings: &mut Vec<TrimError>) -> Result<Self, TrimError> {
+        let buffer = fs::read_to_string(path).map_err(io_error(path, "read lock file"))?;
+        let data = toml::from_str::<LockData>(&buffer).map_err(|source| TrimError::Toml {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let checksums = data
+            .metadata
+            .iter()
+            .filter_map(|(key, value)| {
+                let checksum = value.as_str().filter(|checksum| *checksum != "<none>")?;
+                let dependency = LockDependency::parse(key.strip_prefix(CHECKSUM_PREFIX)?)?;
+                Some((dependency, checksum))
+            })
+            .collect::<Vec<_>>();
+        let version = data
+            .version
+            .unwrap_or(if data.root.is_some() || !checksums.is_empty() {
+                1
+            } else {
+                2
+            });
+        if version > LATEST_LOCK_VERSION {
+            warnings.push(TrimError::LockFile {
+                path: path.to_path_buf(),
+                message: format!(
+                    "version {version} is newer than supported version {LATEST_LOCK_VERSION}"
+                ),
+            });
+        }
+        let mut packages = Vec::new();
+        for package in data.root.into_iter().chain(data.package) {
+            let (Some(name), Some(package_version)) = (package.name, package.version) else {
+                warnings.push(TrimError::LockFile {
+                    path: path.to_path_buf(),
+                    message: "package without name or version is skipped".to_string(),
+                });
+                continue;
+            };
+            let mut lock_package = LockPackage {
+                name,
+                version: package_version,
+                source: package.source,
+                checksum: package.checksum,
+                dependencies: package
+                    .dependencies
+                    .iter()
+                    .filter_map(|dependency| LockDependency::parse(dependency))
+                    .collect(),
+            };
+            if lock_package.checksum.is_none() {
+                lock_package.checksum = checksums
+                    .iter()
+                    .find(|(dependency, _)| dependency.matches(&lock_package))
+                    .map(|(_, checksum)| (*checksum).to_string());
+            }
+            packages.push(lock_package);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            version,
+            packages,
+        })
+    }
+
+    /// return path of lock file
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// return version of lock file format
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// return packages listed in lock file
+    #[must_use]
+    pub fn packages(&self) -> &[LockPackage] {
+        &self.packages
+    }
+
+    /// list shortest dependency path from root package to each package for
+    /// which filter returns true. Root package is package without source which
+    /// is not dependency of other package i.e member of workspace
+    pub fn dependency_paths<F>(&self, filter: F) -> Vec<Vec<&LockPackage>>
+    where
+        F: Fn(&LockPackage) -> bool,
+    {
+        let mut by_name = HashMap::new();
+        for (index, package) in self.packages.iter().enumerate() {
+            by_name
+                .entry(package.name())
+                .or_insert_with(Vec::new)
+                .push(index);
+        }
+        let dependencies = self
+            .packages
+            .iter()
+            .map(|package| {
+                package
+                    .dependencies()
+                    .iter()
+                    .filter_map(|dependency| {
+                        by_name.get(dependency.name()).and_then(|indexes| {
+                            indexes
+                                .iter()
+                                .copied()
+                                .find(|index| dependency.matches(&self.packages[*index]))
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut roots = (0..self.packages.len())
+            .filter(|index| {
+                self.packages[*index].source().is_none()
+                    && !dependencies.iter().any(|list| list.contains(index))
+            })
+            .collect::<Vec<_>>();
+        // members depending on each other in cycle don't have any root
+        if roots.is_empty() {
+            roots = (0..self.packages.len())
+                .filter(|index| self.packages[*index].source().is_none())
+                .collect();
+        }
+        // breadth first search from all roots so path found is shortest path
+        let mut parent = vec![None; self.packages.len()];
+        let mut visited = vec![false; self.packages.len()];
+        let mut queue = VecDeque::new();
+        for root in roots {
+            visited[root] = true;
+            queue.push_back(root);
+        }
+        let mut order = Vec::new();
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for dependency in &dependencies[index] {
+                if !visited[*dependency] {
+                    visited[*dependency] = true;
+                    parent[*dependency] = Some(index);
+                    queue.push_back(*dependency);
+                }
+            }
+        }
+        order.sort_unstable();
+        order
+            .into_iter()
+            .filter(|index| filter(&self.packages[*index]))
+            .map(|index| {
+                let mut path = vec![&self.packages[index]];
+                let mut current = index;
+                while let Some(previous) = parent[current] {
+                    path.push(&self.packages[previous]);
+                    current = previous;
+                }
+                path.reverse();
+                path
+            })
+            .collect()
+    }
+}
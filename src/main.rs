@@ -4,34 +4,98 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::cast_precision_loss, clippy::too_many_lines)]
 
-mod config_file;
-mod crate_detail;
 mod create_app;
-mod dir_path;
-mod git_dir;
-mod list_crate;
-mod registry_dir;
-#[cfg(test)]
-mod test;
-
-use crate::{
-    config_file::ConfigFile, crate_detail::CrateDetail, dir_path::DirPath, git_dir::GitDir,
-    list_crate::CrateList, registry_dir::RegistryDir,
+
+use cargo_trim::{
+    CargoConfig, ConfigFile, CrateDetail, CrateFilter, CrateId, CrateList, CrateRule, DirPath,
+    FilterMode, GitId, History, HistoryEntry, HistorySummary, IndexKind, KeepPolicy,
+    PackageCacheLock, PlanEntry, Policy, ProjectUsage, QueryReport, Reason, RegistryIndex,
+    RegistryQuery, RemovalPlan, Report, RuleSource, Scanner, Trash, TrashEntry, TrashRun,
+    TrimError,
 };
 use clap::{ArgMatches, Shell};
-use colored::*;
+use colored::Colorize;
 use fs_extra::dir::get_size;
 use pretty_bytes::converter::convert;
-use std::{collections::HashMap, fs, path::PathBuf, process::Command};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// print line which is not part of json report, line is printed to stderr when
+// json report is printed to stdout so stdout only contains report
+macro_rules! show {
+    ($report:expr, $($arg:tt)*) => {
+        if $report.is_some() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 fn main() {
-    // set all dir path
-    let dir_path = DirPath::set_dir_path();
-    let mut file = fs::File::open(dir_path.config_dir().to_str().unwrap())
-        .expect("failed to open config dir folder");
     let app = create_app::app().get_matches();
     let app = app.subcommand_matches("trim").unwrap();
     generate_completions(app);
+
+    // errors which doesn't stop execution are collected and reported at end
+    let mut errors = Vec::new();
+    // report is only created when json format is used which is printed at end
+    let mut report = if global_value(app, "format") == Some("json") {
+        Some(Report::new())
+    } else {
+        None
+    };
+    let result = run(app, &mut errors, &mut report);
+    if let Some(report) = &mut report {
+        for error in &errors {
+            report.add_error(error);
+        }
+        if let Err(error) = &result {
+            report.add_error(error);
+        }
+        match report.to_json() {
+            Ok(json) => println!("{json}"),
+            Err(error) => errors.push(error),
+        }
+    }
+    if !errors.is_empty() {
+        eprintln!(
+            "{}",
+            format!("Failed to complete {} operation", errors.len()).red()
+        );
+        for error in &errors {
+            eprintln!("  \u{251c} {error}");
+        }
+    }
+    match result {
+        Err(error) => {
+            eprintln!("{} {}", "error:".red().bold(), error);
+            process::exit(error.exit_code());
+        }
+        Ok(()) => {
+            if let Some(error) = errors.first() {
+                process::exit(error.exit_code());
+            }
+        }
+    }
+}
+
+// Perform all operation of trim subcommand
+fn run(
+    app: &ArgMatches,
+    errors: &mut Vec<TrimError>,
+    report: &mut Option<Report>,
+) -> Result<(), TrimError> {
+    // set all dir path
+    let dir_path = DirPath::set_dir_path(
+        global_value(app, "cargo home"),
+        global_value(app, "config file"),
+    )?;
     let mut git_subcommand = &ArgMatches::new();
     let mut registry_subcommand = &ArgMatches::new();
     if app.is_present("git") {
@@ -46,96 +110,210 @@ fn main() {
     let dry_run_registry = registry_subcommand.is_present("dry run");
 
     // Perform all modification of config file flag and subcommand operation
-    let config_file = config_file::modify_config_file(&mut file, app, &dir_path.config_dir());
+    let mut config_file = ConfigFile::init(dir_path.config_dir())?;
+    modify_config_file(&mut config_file, app, report.as_ref())?;
+    config_file.save(dir_path.config_dir())?;
+    let policy = config_file.policy();
+
+    // Use json report when format policy of config file is json and --format is
+    // not passed
+    if global_value(app, "format").is_none() {
+        match policy.format() {
+            Some("json") => *report = Some(Report::new()),
+            Some("table") | None => (),
+            Some(format) => {
+                return Err(TrimError::InvalidValue {
+                    argument: "format policy",
+                    value: format.to_string(),
+                })
+            }
+        }
+    }
 
     // Perform action of removing config file with -c flag
-    clear_config(&app, &dir_path);
+    clear_config(app, &dir_path, report.as_ref())?;
+
+    // Parse include and exclude rules of config file and environment variable
+    let mut crate_filter = CrateFilter::new(&config_file)?;
 
     // Query about config file information
-    config_subcommand(&app, &config_file);
+    config_subcommand(app, &config_file, report.as_ref());
+
+    // Lock package cache before any removal so cargo doesn't extract or checkout
+    // crate into directory which is being removed, lock is acquired only once
+    // some operation is going to modify cargo home
+    let mut package_cache_lock = PackageCacheLock::new(dir_path.cargo_home());
+    let wait = !global_present(app, "no wait");
+    let dry_run = dry_run_app || dry_run_git || dry_run_registry;
+
+    // Move removed path to trash instead of deleting them in trash mode
+    let mut trash_run = global_present(app, "trash mode").then(|| TrashRun::new(&dir_path));
+
+    // Record all path removed by run which is appended to history file at end
+    let mut history = HistoryEntry::new(std::env::args().skip(1).collect());
+
+    // Restore removed path from trash and purge trash
+    undo_subcommand(
+        app,
+        &dir_path,
+        (&mut package_cache_lock, wait),
+        (report.as_ref(), errors),
+    )?;
+    trash_subcommand(app, &dir_path, report.as_ref())?;
+
+    // Show history of previous run
+    history_subcommand(app, &dir_path, report, errors)?;
 
     // Force remove all crates without reading config file also remove index .cache
     // folder
     let force_remove_app = app.is_present("force remove");
     let force_remove_git = git_subcommand.is_present("force remove");
     let force_remove_registry = registry_subcommand.is_present("force remove");
+    if (force_remove_app || force_remove_git || force_remove_registry) && !dry_run {
+        acquire_lock(&mut package_cache_lock, wait)?;
+    }
     force_remove(
         &dir_path,
         (force_remove_app, force_remove_git, force_remove_registry),
         (dry_run_app, dry_run_git, dry_run_registry),
+        &mut trash_run,
+        &mut history,
+        report,
+        errors,
     );
 
     // Perform git compress to .cargo/index
-    git_compress(
-        &app,
-        &dir_path.index_dir(),
-        &dir_path.checkout_dir(),
-        &dir_path.db_dir(),
-    );
+    if app.is_present("git compress") {
+        acquire_lock(&mut package_cache_lock, wait)?;
+    }
+    git_compress(app, &dir_path, &mut history, (report.as_ref(), errors))?;
 
     // Perform light cleanup
     let light_cleanup_app = app.is_present("light cleanup");
     let light_cleanup_git = git_subcommand.is_present("light cleanup");
-    let light_cleanup_registry = registry_subcommand.is_present("light_cleanup");
+    let light_cleanup_registry = registry_subcommand.is_present("light cleanup");
+    if (light_cleanup_app || light_cleanup_git || light_cleanup_registry) && !dry_run {
+        acquire_lock(&mut package_cache_lock, wait)?;
+    }
     light_cleanup(
-        &dir_path.checkout_dir(),
-        &dir_path.src_dir(),
-        &dir_path.index_dir(),
+        &dir_path,
         (light_cleanup_app, light_cleanup_git, light_cleanup_registry),
         (dry_run_app, dry_run_git, dry_run_registry),
+        &mut trash_run,
+        &mut history,
+        report,
+        errors,
     );
 
     // Wipe a certain folder all together
-    wipe_directory(&app, &dir_path);
+    if app.is_present("wipe") && !dry_run_app {
+        acquire_lock(&mut package_cache_lock, wait)?;
+    }
+    wipe_directory(app, &dir_path, &mut trash_run, (&mut history, report))?;
 
     // create new CrateDetail struct
     let mut crate_detail = CrateDetail::new();
 
+    // Scan project directory with scan cache and depth limit
+    let mut scanner = Scanner::new();
+    scanner.set_cache_file(dir_path.scan_cache());
+    if let Some(value) = global_value(app, "max depth") {
+        let max_depth = value
+            .parse::<usize>()
+            .map_err(|_| TrimError::InvalidValue {
+                argument: "--max-depth",
+                value: value.to_string(),
+            })?;
+        scanner.set_max_depth(Some(max_depth));
+    }
+
     // List out crates
-    let list_crate = list_crate::CrateList::create_list(&dir_path, &config_file, &mut crate_detail);
-
-    // Get Location where registry crates and git crates are stored out by cargo
-    let mut registry_crates_location = registry_dir::RegistryDir::new(
-        &dir_path.cache_dir(),
-        &dir_path.src_dir(),
-        &dir_path.index_dir(),
-        list_crate.installed_registry(),
-        dry_run_app || dry_run_registry,
-    );
+    let mut list_crate =
+        CrateList::create_list(&dir_path, &config_file, &scanner, &mut crate_detail, errors);
+    for warning in list_crate.warnings() {
+        if let Some(report) = report {
+            report.add_warning(warning);
+        } else {
+            eprintln!("{} {}", "warning:".yellow().bold(), warning);
+        }
+    }
+    list_crate.set_keep_policy(keep_policy(app, policy)?, &crate_detail);
 
-    let git_crates_location = git_dir::GitDir::new(
-        &dir_path.checkout_dir(),
-        &dir_path.db_dir(),
-        dry_run_app || dry_run_git,
-    );
+    // Protect crates listed in project config file found while scanning
+    // directory
+    crate_filter.add_project_rules(list_crate.cargo_toml_location().project_configs())?;
 
-    // Perform action on list subcommand
-    list_subcommand(app, &list_crate, &crate_detail);
+    // Test crate against include, exclude and project protect rules
+    if let Some(crate_name) = app
+        .subcommand_matches("config")
+        .and_then(|matches| matches.value_of("test"))
+    {
+        test_crate_filter(&crate_filter, crate_name, report.as_ref());
+    }
 
-    // Perform action on -o flag matches which remove all old crates
-    let old_app = app.is_present("old clean");
-    let old_registry = registry_subcommand.is_present("old clean");
-    let old_git = git_subcommand.is_present("old clean");
-    old_clean(
-        &list_crate,
-        (old_app, old_registry, old_git),
-        &mut registry_crates_location,
-        &git_crates_location,
-        &crate_detail,
-    );
+    // Map registry directory to url and keep only crates of filtered registry
+    let cargo_config = CargoConfig::load(dir_path.cargo_home()).unwrap_or_else(|e| {
+        errors.push(e);
+        CargoConfig::new()
+    });
+    let registry_filter = global_value(app, "registry name");
+    if let Some(filter) = registry_filter {
+        list_crate.filter_registry(|registry| cargo_config.matches(registry, filter));
+    }
 
-    // Orphan clean a crates which is not present in directory stored in directory
-    // value of config file on -x flag
-    let orphan_app = app.is_present("orphan clean");
-    let orphan_git = git_subcommand.is_present("orphan clean");
-    let orphan_registry = registry_subcommand.is_present("orphan clean");
-    orphan_clean(
+    // Perform action on list subcommand
+    list_subcommand(
+        app,
+        (&cargo_config, policy),
         &list_crate,
-        (orphan_app, orphan_git, orphan_registry),
-        &mut registry_crates_location,
-        &git_crates_location,
         &crate_detail,
-    );
+        report,
+    )?;
+
+    // Show which project pulls in crate
+    why_subcommand(app, &list_crate, &crate_filter, report.as_ref())?;
+
+    // Create single removal plan for -o, -x, -a and -r flag so same crate is
+    // not removed and counted multiple time and execute it
+    let mut removal_plan = RemovalPlan::new(&dir_path, &list_crate, &crate_detail)?;
+    if let Some(filter) = registry_filter {
+        removal_plan.filter_registry(|registry| cargo_config.matches(registry, filter));
+    }
+    let plan_requested = plan_removal(
+        &mut removal_plan,
+        (&crate_filter, policy),
+        (app, git_subcommand, registry_subcommand),
+    )?;
+    let budget = plan_budget(app, &dir_path, (&crate_filter, policy), &mut removal_plan)?;
+    if plan_requested || budget.is_some() {
+        if !dry_run {
+            acquire_lock(&mut package_cache_lock, wait)?;
+        }
+        let removed = removal_plan.execute(dry_run, trash_run.as_mut(), errors);
+        for entry in &removed {
+            history.add(
+                entry.path(),
+                &entry.kind().to_string(),
+                Some(entry.crate_name()),
+                entry.size(),
+            );
+        }
+        let trash_id = trash_id(trash_run.as_ref(), dry_run);
+        if let Some(report) = report {
+            report.add_removal(dry_run, trash_id, &removed);
+        } else {
+            show_removed(&removed, dry_run, trash_id.is_some());
+        }
+        if let Some((budget, current_size)) = budget {
+            let removed_size = removed.iter().map(|entry| entry.size()).sum::<u64>();
+            let size = current_size.saturating_sub(removed_size);
+            if let Some(report) = report {
+                report.set_budget(budget, size);
+            } else {
+                show_budget(budget, size);
+            }
+        }
+    }
 
     // Perform action for -q flag
     let query_size_app = app.is_present("query size");
@@ -144,37 +322,55 @@ fn main() {
     query_size(
         &dir_path,
         (query_size_app, query_size_git, query_size_registry),
+        (&cargo_config, registry_filter),
         &list_crate,
         &crate_detail,
+        report,
     );
 
-    // Remove all crates by following config file
-    let all_app = app.is_present("all");
-    let all_git = git_subcommand.is_present("all");
-    let all_registry = registry_subcommand.is_present("all");
-    remove_all(
-        &list_crate,
-        &config_file,
-        &mut registry_crates_location,
-        &git_crates_location,
-        (all_app, all_git, all_registry),
+    // Show top crates
+    top_crates(
+        app,
+        (git_subcommand, registry_subcommand),
         &crate_detail,
-    );
+        report,
+    )?;
 
-    // Remove certain crate provided with -r flag
-    remove_crate(
-        &list_crate,
-        (&app, &git_subcommand, &registry_subcommand),
-        &mut registry_crates_location,
-        &git_crates_location,
-        &crate_detail,
-    );
+    if let Some(trash_run) = trash_run.filter(|trash_run| !trash_run.entries().is_empty()) {
+        history.set_trash_id(trash_run.id());
+        if report.is_none() {
+            show_trash_run(&trash_run);
+        }
+    }
 
-    // Show top crates
-    top_crates(&app, &git_subcommand, &registry_subcommand, &crate_detail);
+    // Dry run doesn't modify cargo home so it is not recorded
+    if !dry_run && !history.is_empty() {
+        collect_error(
+            History::new(dir_path.history_file()).append(&history),
+            errors,
+        );
+    }
+
+    // cargo itself acquires package cache lock while updating Cargo.lock
+    package_cache_lock.release();
+    let workspace_roots = list_crate.cargo_toml_location().workspace_roots();
+    update_cargo_toml(app, &workspace_roots, report.as_ref(), errors);
+    Ok(())
+}
 
-    let cargo_toml_location = list_crate.cargo_toml_location().location_path();
-    update_cargo_toml(&app, cargo_toml_location);
+// acquire package cache lock, when other process holds lock message is printed
+// before blocking till it is released
+fn acquire_lock(package_cache_lock: &mut PackageCacheLock, wait: bool) -> Result<(), TrimError> {
+    match package_cache_lock.acquire(false) {
+        Err(TrimError::Locked { path }) if wait => {
+            eprintln!(
+                "Blocking waiting for file lock on package cache {}",
+                path.display()
+            );
+            package_cache_lock.acquire(true)
+        }
+        result => result,
+    }
 }
 
 // Generate out completions script for different shell
@@ -216,209 +412,741 @@ fn generate_completions(app: &ArgMatches) {
     }
 }
 
+// Modify config file value from flag and subcommand passed
+fn modify_config_file(
+    config_file: &mut ConfigFile,
+    app: &ArgMatches,
+    report: Option<&Report>,
+) -> Result<(), TrimError> {
+    let current_dir = std::env::current_dir().map_err(|source| TrimError::Io {
+        path: PathBuf::from("."),
+        operation: "read current directory",
+        source,
+    })?;
+    let current_dir = current_dir.to_string_lossy();
+
+    // add working directory to config
+    if app.is_present("init") {
+        config_file.add_directory(&current_dir);
+    }
+
+    // Add new value in config file, include and exclude value is validated so
+    // invalid pattern is never saved
+    for &name in &["set directory", "exclude", "include"] {
+        if let Some(values) = app.values_of(name) {
+            for value in values {
+                match name {
+                    "set directory" => config_file.add_directory(value),
+                    "exclude" => {
+                        CrateRule::parse(value, RuleSource::ConfigFile, "--exclude")?;
+                        config_file.add_exclude(value);
+                    }
+                    "include" => {
+                        CrateRule::parse(value, RuleSource::ConfigFile, "--include")?;
+                        config_file.add_include(value);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // set policy mode of include and exclude list
+    if let Some(mode) = app.value_of("set mode").and_then(FilterMode::from_name) {
+        config_file.set_mode(mode);
+    }
+
+    // clear working directory from config file
+    if app.is_present("clear") {
+        let subcommand = app.subcommand_matches("clear").unwrap();
+        let dry_run = app.is_present("dry run") || subcommand.is_present("dry run");
+        if !dry_run {
+            config_file.remove_directory(&current_dir);
+        }
+        show_config_removal(&current_dir, dry_run, report);
+    }
+
+    // remove value from config file
+    if app.is_present("remove") {
+        let subcommand = app.subcommand_matches("remove").unwrap();
+        let dry_run = app.is_present("dry run") || subcommand.is_present("dry run");
+        for &name in &["directory", "exclude", "include"] {
+            if let Some(value) = subcommand.value_of(name) {
+                if !dry_run {
+                    match name {
+                        "directory" => config_file.remove_directory(value),
+                        "exclude" => config_file.remove_exclude(value),
+                        "include" => config_file.remove_include(value),
+                        _ => (),
+                    }
+                }
+                show_config_removal(value, dry_run, report);
+            }
+        }
+    }
+    Ok(())
+}
+
+// print value removed from config file
+fn show_config_removal(value: &str, dry_run: bool, report: Option<&Report>) {
+    if dry_run {
+        show!(
+            report,
+            "{} {} {:?}",
+            "Dry run:".yellow(),
+            "removed".red(),
+            value
+        );
+    } else {
+        show!(report, "{} {:?}", "Removed".red(), value);
+    }
+}
+
+// Get value of global argument either passed to trim or any of its nested
+// subcommand
+fn global_value<'a>(app: &'a ArgMatches, name: &str) -> Option<&'a str> {
+    app.value_of(name).or_else(|| {
+        app.subcommand()
+            .1
+            .and_then(|subcommand| global_value(subcommand, name))
+    })
+}
+
+// Check if global flag is present in trim or its nested subcommand
+fn global_present(app: &ArgMatches, name: &str) -> bool {
+    app.is_present(name)
+        || app
+            .subcommand()
+            .1
+            .is_some_and(|subcommand| global_present(subcommand, name))
+}
+
+// Create keep policy used for listing old crates from --keep and
+// --keep-per-major flag, keep policy of config file is used when --keep is not
+// passed
+fn keep_policy(app: &ArgMatches, policy: &Policy) -> Result<KeepPolicy, TrimError> {
+    let count = match global_value(app, "keep") {
+        Some(value) => value
+            .parse::<usize>()
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or_else(|| TrimError::InvalidValue {
+                argument: "--keep",
+                value: value.to_string(),
+            })?,
+        None => match policy.keep() {
+            Some(0) => {
+                return Err(TrimError::InvalidValue {
+                    argument: "keep policy",
+                    value: "0".to_string(),
+                })
+            }
+            Some(count) => count,
+            None => 1,
+        },
+    };
+    Ok(KeepPolicy::new(
+        count,
+        global_present(app, "keep per major"),
+    ))
+}
+
 // Clear config file data
-fn clear_config(app: &ArgMatches, dir_path: &DirPath) {
+fn clear_config(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    report: Option<&Report>,
+) -> Result<(), TrimError> {
     if app.is_present("clear config") {
         if app.is_present("dry run") {
-            println!("{} Cleared config file", "Dry run:".yellow());
+            show!(report, "{} Cleared config file", "Dry run:".yellow());
         } else {
-            fs::remove_file(dir_path.config_dir().as_path()).expect("failed to delete config file");
-            println!("Cleared config file");
+            let config_file = dir_path.config_dir();
+            fs::remove_file(config_file).map_err(|source| TrimError::Io {
+                path: config_file.clone(),
+                operation: "delete config file",
+                source,
+            })?;
+            show!(report, "Cleared config file");
         }
     }
+    Ok(())
 }
 
-// Git compress git files
-fn git_compress(app: &ArgMatches, index_dir: &PathBuf, checkout_dir: &PathBuf, db_dir: &PathBuf) {
+// Git compress git files, repository which failed to compress is pushed to
+// errors
+fn git_compress(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    history: &mut HistoryEntry,
+    (report, errors): (Option<&Report>, &mut Vec<TrimError>),
+) -> Result<(), TrimError> {
     if app.is_present("git compress") {
         let value = app.value_of("git compress").unwrap();
-        if (value == "index" || value == "all") && index_dir.exists() {
-            for entry in fs::read_dir(index_dir).expect("failed to read registry index folder") {
-                let repo_path = entry.unwrap().path();
-                let file_name = repo_path
-                    .file_name()
-                    .expect("Failed to get a file name / folder name");
-                println!(
+        let index_dir = dir_path.index_dir();
+        let checkout_dir = dir_path.checkout_dir();
+        let db_dir = dir_path.db_dir();
+        if value == "index" || value == "all" {
+            for registry_index in RegistryIndex::list(index_dir)? {
+                // sparse index is not a git repository so it cannot be compressed
+                if registry_index.kind() == IndexKind::Sparse {
+                    show!(
+                        report,
+                        "{}",
+                        format!("Skipping {} sparse registry index", registry_index.name())
+                            .yellow()
+                    );
+                    continue;
+                }
+                show!(
+                    report,
                     "{}",
-                    format!("Compressing {} registry index", file_name.to_str().unwrap())
-                        .bright_blue()
+                    format!("Compressing {} registry index", registry_index.name()).bright_blue()
                 );
-                run_git_compress_commands(&repo_path);
+                compress_repo(registry_index.path(), history, (report, errors));
             }
         }
         if value.contains("git") || value == "all" {
             if (value == "git" || value == "git-checkout") && checkout_dir.exists() {
-                for entry in fs::read_dir(checkout_dir).expect("failed to read checkout directory")
-                {
-                    let repo_path = entry.unwrap().path();
-                    for rev in fs::read_dir(repo_path)
-                        .expect("failed to read checkout directory sub directory")
-                    {
-                        let rev_path = rev.unwrap().path();
-                        println!("{}", "Compressing git checkout".bright_blue());
-                        run_git_compress_commands(&rev_path)
+                for repo_path in read_dir(checkout_dir)? {
+                    for rev_path in read_dir(&repo_path)? {
+                        show!(report, "{}", "Compressing git checkout".bright_blue());
+                        compress_repo(&rev_path, history, (report, errors));
                     }
                 }
             }
             if (value == "git" || value == "git-db") && db_dir.exists() {
-                for entry in fs::read_dir(db_dir).expect("failed to read db dir") {
-                    let repo_path = entry.unwrap().path();
-                    println!("{}", "Compressing git db".bright_blue());
-                    run_git_compress_commands(&repo_path);
+                for repo_path in read_dir(db_dir)? {
+                    show!(report, "{}", "Compressing git db".bright_blue());
+                    compress_repo(&repo_path, history, (report, errors));
                 }
             }
         }
-        println!("{}", "Git compress task completed".bright_blue());
+        show!(report, "{}", "Git compress task completed".bright_blue());
     }
+    Ok(())
 }
 
-// run combination of commands which git compress a index of registry
-fn run_git_compress_commands(repo_path: &PathBuf) {
-    // Remove history of all checkout which will help in remove dangling commits
-    if let Err(e) = Command::new("git")
-        .arg("reflog")
-        .arg("expire")
-        .arg("--expire=now")
-        .arg("--all")
-        .current_dir(repo_path)
-        .output()
-    {
-        panic!(format!("git reflog failed to execute due to error {}", e));
-    } else {
-        println!("{:70}.......Step 1/3", "  \u{251c} Completed git reflog");
-    }
-
-    // pack refs of branches/tags etc into one file know as pack-refs file for
-    // effective repo access
-    if let Err(e) = Command::new("git")
-        .arg("pack-refs")
-        .arg("--all")
-        .arg("--prune")
-        .current_dir(repo_path)
-        .output()
-    {
-        panic!(format!(
-            "git pack-refs failed to execute due to error {}",
-            e
-        ));
-    } else {
-        println!(
-            "{:70}.......Step 2/3",
-            "  \u{251c} Packed refs and tags successfully"
-        );
+// git compress repository and print result of each step along with bytes
+// saved which is recorded in history, repository which failed to compress is
+// pushed to errors
+fn compress_repo(
+    repo_path: &Path,
+    history: &mut HistoryEntry,
+    (report, errors): (Option<&Report>, &mut Vec<TrimError>),
+) {
+    match cargo_trim::git_compress(repo_path) {
+        Ok(summary) => {
+            show!(
+                report,
+                "{:70}.......Step 1/3",
+                format!(
+                    "  \u{251c} Expired reflog of {} refs",
+                    summary.expired_refs()
+                )
+            );
+            show!(
+                report,
+                "{:70}.......Step 2/3",
+                format!(
+                    "  \u{251c} Packed {} objects into single pack",
+                    summary.packed_objects()
+                )
+            );
+            show!(
+                report,
+                "{:70}.......Step 3/3",
+                format!(
+                    "  \u{2514} Removed loose objects and {} old packs",
+                    summary.removed_packs()
+                )
+            );
+            let saved = summary.saved();
+            show!(
+                report,
+                "{}",
+                format!(
+                    "Saved {} by compressing {}",
+                    convert(saved as f64),
+                    repo_path.display()
+                )
+                .bright_blue()
+            );
+            if saved > 0 {
+                history.add(repo_path, "git compress", None, saved);
+            }
+        }
+        Err(e) => errors.push(e),
     }
+}
 
-    // cleanup unnecessary file and optimize a local repo
-    if let Err(e) = Command::new("git")
-        .arg("gc")
-        .arg("--aggressive")
-        .arg("--prune=now")
-        .current_dir(repo_path)
-        .output()
-    {
-        panic!(format!("git gc failed to execute due to error {}", e));
-    } else {
-        println!(
-            "{:70}.......Step 3/3",
-            "  \u{2514} Cleaned up unnecessary files and optimize a files"
-        );
-    }
+// list out path of all entries present inside directory
+fn read_dir(path: &Path) -> Result<Vec<PathBuf>, TrimError> {
+    fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect()
+        })
+        .map_err(|source| TrimError::Io {
+            path: path.to_path_buf(),
+            operation: "read directory",
+            source,
+        })
 }
 
 // light cleanup registry directory
 fn light_cleanup(
-    checkout_dir: &PathBuf,
-    src_dir: &PathBuf,
-    index_dir: &PathBuf,
+    dir_path: &DirPath,
     (light_cleanup_app, light_cleanup_git, light_cleanup_registry): (bool, bool, bool),
     (dry_run_app, dry_run_git, dry_run_registry): (bool, bool, bool),
+    trash_run: &mut Option<TrashRun>,
+    history: &mut HistoryEntry,
+    report: &mut Option<Report>,
+    errors: &mut Vec<TrimError>,
 ) {
     if light_cleanup_app || light_cleanup_git || light_cleanup_registry {
         if light_cleanup_app || light_cleanup_registry {
             let dry_run = dry_run_app || dry_run_registry;
-            delete_folder(checkout_dir, dry_run);
+            remove_folder(
+                (dir_path.src_dir(), "registry source"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
             // Delete out .cache folder also
-            for entry in fs::read_dir(index_dir).expect("failed to read out index directory") {
-                let entry = entry.unwrap().path();
-                let registry_dir = entry.as_path();
-                for folder in
-                    fs::read_dir(registry_dir).expect("failed to read out registry directory")
-                {
-                    let folder = folder.unwrap().path();
-                    let folder_name = folder
-                        .file_name()
-                        .expect("failed to get file name form registry directory sub folder");
-                    if folder_name == ".cache" {
-                        delete_folder(&folder, dry_run);
-                    }
-                }
-            }
+            remove_index_cache(
+                dir_path.index_dir(),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
         }
         if light_cleanup_app || light_cleanup_git {
             let dry_run = dry_run_app || dry_run_git;
-            delete_folder(src_dir, dry_run);
+            remove_folder(
+                (dir_path.checkout_dir(), "git checkout"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
+        }
+    }
+}
+
+// push error of result to errors list
+fn collect_error(result: Result<(), TrimError>, errors: &mut Vec<TrimError>) {
+    if let Err(e) = result {
+        errors.push(e);
+    }
+}
+
+// remove folder of cleanup, print it and record its size in history. Error is
+// pushed to errors list
+fn remove_folder(
+    (folder, category): (&Path, &str),
+    dry_run: bool,
+    trash_run: &mut Option<TrashRun>,
+    (history, report): (&mut HistoryEntry, &mut Option<Report>),
+    errors: &mut Vec<TrimError>,
+) {
+    match cargo_trim::delete_folder(folder, dry_run, trash_run.as_mut()) {
+        Ok(Some(size)) => {
+            record_folder(
+                (folder, category, size),
+                (dry_run, trash_id(trash_run.as_ref(), dry_run)),
+                (history, report),
+            );
+        }
+        Ok(None) => {}
+        Err(e) => errors.push(e),
+    }
+}
+
+// remove .cache folder of all registry index, print them and record their size
+// in history. Error is pushed to errors list
+fn remove_index_cache(
+    index_dir: &Path,
+    dry_run: bool,
+    trash_run: &mut Option<TrashRun>,
+    (history, report): (&mut HistoryEntry, &mut Option<Report>),
+    errors: &mut Vec<TrimError>,
+) {
+    match cargo_trim::delete_index_cache(index_dir, dry_run, trash_run.as_mut()) {
+        Ok(removed) => {
+            for (folder, size) in removed {
+                record_folder(
+                    (&folder, "index cache", size),
+                    (dry_run, trash_id(trash_run.as_ref(), dry_run)),
+                    (history, report),
+                );
+            }
         }
+        Err(e) => errors.push(e),
+    }
+}
+
+// get id of trash run in which removed path is moved, nothing is moved on dry
+// run
+fn trash_id(trash_run: Option<&TrashRun>, dry_run: bool) -> Option<&str> {
+    trash_run.filter(|_| !dry_run).map(TrashRun::id)
+}
+
+// record folder removed by cleanup in history and report, folder is printed
+// when report is not used
+fn record_folder(
+    (folder, category, size): (&Path, &str, u64),
+    (dry_run, trash_id): (bool, Option<&str>),
+    (history, report): (&mut HistoryEntry, &mut Option<Report>),
+) {
+    history.add(folder, category, None, size);
+    if let Some(report) = report {
+        report.add_removed_folder((dry_run, trash_id), folder, category, size);
+    } else {
+        show_removed_folder(folder, dry_run, trash_id.is_some());
+    }
+}
+
+// print folder removed by cleanup
+fn show_removed_folder(folder: &Path, dry_run: bool, trash: bool) {
+    if dry_run {
+        println!(
+            "{} {} {}",
+            "Dry run:".yellow(),
+            "removed".red(),
+            folder.display()
+        );
+    } else if trash {
+        println!("{} {}", "Moved to trash".yellow(), folder.display());
+    } else {
+        println!("{} {}", "Removed".red(), folder.display());
     }
 }
 
 // Perform different operation for a list subcommand
-fn list_subcommand(app: &ArgMatches, list_crate: &CrateList, crate_detail: &CrateDetail) {
+fn list_subcommand(
+    app: &ArgMatches,
+    (cargo_config, policy): (&CargoConfig, &Policy),
+    list_crate: &CrateList,
+    crate_detail: &CrateDetail,
+    report: &mut Option<Report>,
+) -> Result<(), TrimError> {
     if app.is_present("list") {
         let list_subcommand = app.subcommand_matches("list").unwrap();
-        if list_subcommand.is_present("old") {
-            list_crate_type(
-                crate_detail,
+        let list_types = [
+            (
+                "old",
                 list_crate.old_registry(),
+                list_crate.old_git(),
                 "REGISTRY OLD CRATE",
-            );
-            list_crate_type(crate_detail, list_crate.old_git(), "GIT OLD CRATE");
-        }
-        if list_subcommand.is_present("orphan") {
-            list_crate_type(
-                crate_detail,
+                "GIT OLD CRATE",
+            ),
+            (
+                "orphan",
                 list_crate.orphan_registry(),
+                list_crate.orphan_git(),
                 "REGISTRY ORPHAN CRATE",
-            );
-            list_crate_type(crate_detail, list_crate.orphan_git(), "GIT ORPHAN CRATE");
-        }
-        if list_subcommand.is_present("used") {
-            list_crate_type(
-                crate_detail,
+                "GIT ORPHAN CRATE",
+            ),
+            (
+                "used",
                 list_crate.used_registry(),
+                list_crate.used_git(),
                 "REGISTRY USED CRATE",
-            );
-            list_crate_type(crate_detail, list_crate.used_git(), "GIT USED CRATE");
-        }
-        if list_subcommand.is_present("all") {
-            list_crate_type(
-                crate_detail,
+                "GIT USED CRATE",
+            ),
+            (
+                "all",
                 list_crate.installed_registry(),
-                "REGISTRY INSTALLED CRATE",
-            );
-            list_crate_type(
-                crate_detail,
                 list_crate.installed_git(),
+                "REGISTRY INSTALLED CRATE",
                 "GIT INSTALLED CRATE",
-            );
+            ),
+        ];
+        for (flag, registry_list, git_list, registry_title, git_title) in &list_types {
+            if list_subcommand.is_present(flag) {
+                if let Some(report) = report {
+                    report.add_registry_crates(
+                        cargo_config,
+                        list_crate,
+                        crate_detail,
+                        registry_list,
+                    );
+                    report.add_git_crates(list_crate, crate_detail, git_list);
+                } else {
+                    list_registry_crate_type(
+                        cargo_config,
+                        (list_crate, crate_detail),
+                        registry_list,
+                        registry_title,
+                    );
+                    list_git_crate_type((list_crate, crate_detail), git_list, git_title);
+                }
+            }
+        }
+        if list_subcommand.is_present("max age") {
+            let max_age = max_age(list_subcommand.value_of("max age"), policy)?;
+            let registry_list = list_crate.stale_registry(crate_detail, max_age);
+            let git_list = list_crate.stale_git(crate_detail, max_age);
+            if let Some(report) = report {
+                report.add_registry_crates(cargo_config, list_crate, crate_detail, &registry_list);
+                report.add_git_crates(list_crate, crate_detail, &git_list);
+            } else {
+                list_registry_crate_type(
+                    cargo_config,
+                    (list_crate, crate_detail),
+                    &registry_list,
+                    "REGISTRY STALE CRATE",
+                );
+                list_git_crate_type((list_crate, crate_detail), &git_list, "GIT STALE CRATE");
+            }
+        }
+        if list_subcommand.is_present("by project") {
+            let usage = list_crate.project_usage(crate_detail);
+            if let Some(report) = report {
+                report.add_projects(&usage);
+            } else {
+                list_project_usage(&usage);
+            }
         }
     }
+    Ok(())
 }
 
-// list certain crate type to terminal
-fn list_crate_type(crate_detail: &CrateDetail, crate_type: &[String], title: &str) {
-    show_title(title);
+// Show dependency path from root package of each lock file to crate along with
+// exclude rule protecting crate
+fn why_subcommand(
+    app: &ArgMatches,
+    list_crate: &CrateList,
+    crate_filter: &CrateFilter,
+    report: Option<&Report>,
+) -> Result<(), TrimError> {
+    if let Some(matches) = app.subcommand_matches("why") {
+        let value = matches.value_of("crate").unwrap();
+        let (name, version) = match value.split_once('@') {
+            Some((name, version)) => (
+                name,
+                Some(
+                    semver::Version::parse(version).map_err(|_| TrimError::InvalidValue {
+                        argument: "<crate>",
+                        value: value.to_string(),
+                    })?,
+                ),
+            ),
+            None => (value, None),
+        };
+        // commit of git crate used by lock file which is matched with checkout
+        // since name of git db is name of repo instead of package
+        let mut commits = BTreeSet::new();
+        let mut found = false;
+        for lock_file in list_crate.lock_files() {
+            let paths = lock_file.dependency_paths(|package| {
+                package.name() == name
+                    && version.as_ref().is_none_or(|version| {
+                        semver::Version::parse(package.version()).is_ok_and(|v| v == *version)
+                    })
+            });
+            if paths.is_empty() {
+                continue;
+            }
+            found = true;
+            show!(
+                report,
+                "{} {} (version {})",
+                "Lock file:".bold(),
+                lock_file.path().display(),
+                lock_file.version()
+            );
+            for path in &paths {
+                let chain = path
+                    .iter()
+                    .map(|package| format!("{} {}", package.name(), package.version()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                show!(report, "  \u{251c} {chain}");
+                let commit = path
+                    .last()
+                    .and_then(|package| package.source())
+                    .and_then(|source| source.strip_prefix("git+"))
+                    .and_then(|source| source.rsplit_once('#'))
+                    .map(|(_, commit)| commit.to_string());
+                commits.extend(commit);
+            }
+        }
+        if !found {
+            show!(
+                report,
+                "{}",
+                format!("No lock file of project directory uses {value}").bright_blue()
+            );
+        }
 
-    let mut total_size = 0.0;
-    for crates in crate_type {
-        let size = crate_detail.find(crates, title);
-        total_size += size;
-        println!("|{:^40}|{:^10.3}|", crates, size);
+        // installed crate is checked against rule whether lock file uses it or not
+        let registry_crates = list_crate.installed_registry_named(name, version.as_ref());
+        let mut git_crates = if version.is_none() {
+            list_crate.installed_git_named(name)
+        } else {
+            Vec::new()
+        };
+        for git_id in list_crate.installed_git() {
+            if !git_crates.contains(&git_id)
+                && commits.iter().any(|commit| git_id.is_checkout_of(commit))
+            {
+                git_crates.push(git_id);
+            }
+        }
+        if registry_crates.is_empty() && git_crates.is_empty() {
+            show!(
+                report,
+                "{}",
+                format!("{value} is not present in cargo home").bright_blue()
+            );
+        }
+        for crate_id in registry_crates {
+            show_protection(
+                &crate_id.to_string(),
+                crate_filter.registry_excluded_by(crate_id),
+                crate_filter.follow_registry(crate_id),
+                report,
+            );
+        }
+        for git_id in git_crates {
+            show_protection(
+                &git_id.to_string(),
+                crate_filter.git_excluded_by(git_id),
+                crate_filter.follow_git(git_id),
+                report,
+            );
+        }
     }
+    Ok(())
+}
 
-    show_total_count(crate_type, total_size);
+// show exclude rule protecting installed crate or whether crate is kept by
+// allowlist mode
+fn show_protection(
+    full_name: &str,
+    excluded_by: Option<&CrateRule>,
+    follow: bool,
+    report: Option<&Report>,
+) {
+    if let Some(rule) = excluded_by {
+        show!(
+            report,
+            "{full_name} is protected by exclude rule {:?} ({} from {})",
+            rule.value(),
+            rule.kind(),
+            rule.source()
+        );
+    } else if !follow {
+        show!(
+            report,
+            "{full_name} is kept since allowlist mode only removes included crates"
+        );
+    } else {
+        show!(report, "{full_name} is not protected by any rule");
+    }
 }
 
-// show title
+// list size of crates used by each project directory to terminal with separate
+// table for each project
+fn list_project_usage(usage: &[ProjectUsage]) {
+    if usage.is_empty() {
+        println!("{}", "No project directory is set".bright_blue());
+    }
+    let mega_byte = |size: u64| size as f64 / 1000_f64.powf(2.0);
+    for project in usage {
+        println!("{} {}", "Project:".bold(), project.directory().display());
+        show_title("PROJECT USAGE");
+        let rows = [
+            ("registry crates", project.registry_size()),
+            (
+                "registry crates only used by project",
+                project.registry_exclusive_size(),
+            ),
+            ("git crates", project.git_size()),
+            (
+                "git crates only used by project",
+                project.git_exclusive_size(),
+            ),
+        ];
+        for (kind, size) in &rows {
+            println!("|{:^40}|{:^10.3}|", kind, mega_byte(*size));
+        }
+        print_dash();
+    }
+}
+
+// list certain registry crate type to terminal with separate table for each
+// registry
+fn list_registry_crate_type(
+    cargo_config: &CargoConfig,
+    (list_crate, crate_detail): (&CrateList, &CrateDetail),
+    crate_type: &[CrateId],
+    title: &str,
+) {
+    let mut registries = BTreeMap::new();
+    for crate_id in crate_type {
+        registries
+            .entry(crate_id.registry())
+            .or_insert_with(Vec::new)
+            .push((
+                crate_id.to_string(),
+                crate_detail.find_size_registry_all(crate_id),
+                list_crate.registry_workspaces(crate_id),
+            ));
+    }
+    if registries.is_empty() {
+        list_crate_type(&[], title);
+    }
+    for (registry, crates) in &registries {
+        match cargo_config.url(registry) {
+            Some(url) => println!("{} {} ({})", "Registry:".bold(), registry, url),
+            None => println!("{} {}", "Registry:".bold(), registry),
+        }
+        list_crate_type(crates, title);
+    }
+}
+
+// list certain git crate type to terminal
+fn list_git_crate_type(
+    (list_crate, crate_detail): (&CrateList, &CrateDetail),
+    crate_type: &[GitId],
+    title: &str,
+) {
+    let crates = crate_type
+        .iter()
+        .map(|git_id| {
+            (
+                git_id.to_string(),
+                crate_detail.find_size_git_all(git_id),
+                list_crate.git_workspaces(git_id),
+            )
+        })
+        .collect::<Vec<_>>();
+    list_crate_type(&crates, title);
+}
+
+// list crate along with its size and workspaces using it to terminal
+fn list_crate_type(crate_type: &[(String, f64, &[PathBuf])], title: &str) {
+    show_title(title);
+
+    let mut total_size = 0.0;
+    for (crates, size, workspaces) in crate_type {
+        total_size += size;
+        println!("|{crates:^40}|{size:^10.3}|");
+        for workspace in *workspaces {
+            println!("|  {:<38}|{:^10}|", workspace.display(), "");
+        }
+    }
+
+    show_total_count(crate_type, total_size);
+}
+
+// show title
 fn show_title(title: &str) {
     print_dash();
     println!("|{:^40}|{:^10}|", title.bold(), "SIZE(MB)");
@@ -426,7 +1154,7 @@ fn show_title(title: &str) {
 }
 
 // show total count using data and size
-fn show_total_count(data: &[String], size: f64) {
+fn show_total_count<T>(data: &[T], size: f64) {
     if data.is_empty() {
         println!("|{:^40}|{:^10}|", "NONE".red(), "0.000".red());
     }
@@ -434,7 +1162,7 @@ fn show_total_count(data: &[String], size: f64) {
     println!(
         "|{:^40}|{:^10}|",
         format!("Total no of crates:- {}", data.len()).bright_blue(),
-        format!("{:.3}", size).bright_blue()
+        format!("{size:.3}").bright_blue()
     );
     print_dash();
 }
@@ -449,159 +1177,650 @@ fn print_dash() {
     );
 }
 
-// Clean old crates
-fn old_clean(
-    list_crate: &CrateList,
-    (old_app, old_registry, old_git): (bool, bool, bool),
-    registry_crates_location: &mut RegistryDir,
-    git_crates_location: &GitDir,
-    crate_detail: &CrateDetail,
-) {
-    if old_app || old_registry || old_git {
-        let mut size_cleaned = 0.0;
-        if old_app || old_registry {
-            size_cleaned += registry_crates_location
-                .remove_crate_list(&crate_detail, list_crate.old_registry());
-        }
-        if old_app || old_git {
-            size_cleaned +=
-                git_crates_location.remove_crate_list(&crate_detail, list_crate.old_git());
-        }
-        println!(
-            "{}",
-            format!("Total size of old crates removed :- {:.3} MB", size_cleaned).bright_blue()
+// Add crates to removal plan for old clean, orphan clean, all, max age and
+// remove-crate flag, return true if any of these flag is passed
+fn plan_removal(
+    removal_plan: &mut RemovalPlan,
+    (crate_filter, policy): (&CrateFilter, &Policy),
+    (app, git_subcommand, registry_subcommand): (&ArgMatches, &ArgMatches, &ArgMatches),
+) -> Result<bool, TrimError> {
+    let old_app = app.is_present("old clean");
+    let old_git = git_subcommand.is_present("old clean");
+    let old_registry = registry_subcommand.is_present("old clean");
+    removal_plan.add_old(crate_filter, old_app || old_registry, old_app || old_git);
+
+    let orphan_app = app.is_present("orphan clean");
+    let orphan_git = git_subcommand.is_present("orphan clean");
+    let orphan_registry = registry_subcommand.is_present("orphan clean");
+    removal_plan.add_orphan(
+        crate_filter,
+        orphan_app || orphan_registry,
+        orphan_app || orphan_git,
+    );
+
+    let all_app = app.is_present("all");
+    let all_git = git_subcommand.is_present("all");
+    let all_registry = registry_subcommand.is_present("all");
+    removal_plan.add_all(crate_filter, all_app || all_registry, all_app || all_git);
+
+    let max_age_app = app.is_present("max age");
+    let max_age_git = git_subcommand.is_present("max age");
+    let max_age_registry = registry_subcommand.is_present("max age");
+    if max_age_app || max_age_git || max_age_registry {
+        let value = app
+            .value_of("max age")
+            .or_else(|| git_subcommand.value_of("max age"))
+            .or_else(|| registry_subcommand.value_of("max age"));
+        let max_age = max_age(value, policy)?;
+        removal_plan.add_stale(
+            crate_filter,
+            max_age,
+            max_age_app || max_age_registry,
+            max_age_app || max_age_git,
         );
     }
-}
 
-// Clean orphan crates
-fn orphan_clean(
-    list_crate: &CrateList,
-    (orphan_app, orphan_git, orphan_registry): (bool, bool, bool),
-    registry_crates_location: &mut RegistryDir,
-    git_crates_location: &GitDir,
-    crate_detail: &CrateDetail,
-) {
-    if orphan_app || orphan_git || orphan_registry {
-        let mut size_cleaned = 0.0;
-        if orphan_app || orphan_registry {
-            size_cleaned += registry_crates_location
-                .remove_crate_list(&crate_detail, list_crate.orphan_registry());
+    let remove_crate_app = app.is_present("remove-crate");
+    let remove_crate_git = git_subcommand.is_present("remove-crate");
+    let remove_crate_registry = registry_subcommand.is_present("remove-crate");
+    if remove_crate_app || remove_crate_git || remove_crate_registry {
+        let value = app.value_of("remove-crate").unwrap_or_else(|| {
+            git_subcommand
+                .value_of("remove-crate")
+                .unwrap_or_else(|| registry_subcommand.value_of("remove-crate").unwrap())
+        });
+        if remove_crate_app || remove_crate_registry {
+            removal_plan.add_registry_crate_named(value, Reason::Requested);
         }
-        if orphan_app || orphan_git {
-            size_cleaned +=
-                git_crates_location.remove_crate_list(&crate_detail, list_crate.orphan_git());
+        if remove_crate_app || remove_crate_git {
+            removal_plan.add_git_crate_named(value, Reason::Requested);
         }
-        println!(
-            "{}",
-            format!(
-                "Total size of orphan crates removed :- {:.3} MB",
-                size_cleaned
-            )
-            .bright_blue()
-        );
     }
+
+    Ok(old_app
+        || old_git
+        || old_registry
+        || orphan_app
+        || orphan_git
+        || orphan_registry
+        || all_app
+        || all_git
+        || all_registry
+        || remove_crate_app
+        || remove_crate_git
+        || remove_crate_registry
+        || max_age_app
+        || max_age_git
+        || max_age_registry)
+}
+
+// parse duration of --max-age, max-age policy of config file is used when flag
+// is passed without value
+fn max_age(value: Option<&str>, policy: &Policy) -> Result<Duration, TrimError> {
+    let value = value
+        .or_else(|| policy.max_age())
+        .ok_or(TrimError::InvalidValue {
+            argument: "--max-age",
+            value: String::new(),
+        })?;
+    cargo_trim::parse_duration(value, "--max-age")
+}
+
+// Add least recently used crates to removal plan until cache fits in budget,
+// return budget along with current size of registry and git directory
+fn plan_budget(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    (crate_filter, policy): (&CrateFilter, &Policy),
+    removal_plan: &mut RemovalPlan,
+) -> Result<Option<(u64, u64)>, TrimError> {
+    if app.is_present("budget") {
+        let value =
+            app.value_of("budget")
+                .or_else(|| policy.budget())
+                .ok_or(TrimError::InvalidValue {
+                    argument: "--budget",
+                    value: String::new(),
+                })?;
+        let budget = cargo_trim::parse_size(value)?;
+        let current_size = get_size(dir_path.registry_dir()).unwrap_or(0_u64)
+            + get_size(dir_path.git_dir()).unwrap_or(0_u64);
+        removal_plan.add_budget(crate_filter, budget, current_size);
+        Ok(Some((budget, current_size)))
+    } else {
+        Ok(None)
+    }
+}
+
+// show size of registry and git directory compared with budget
+fn show_budget(budget: u64, size: u64) {
+    let status = if size > budget {
+        format!("{} over budget", convert((size - budget) as f64)).red()
+    } else {
+        format!("{} under budget", convert((budget - size) as f64)).bright_blue()
+    };
+    println!(
+        "{} {} of {} budget ({})",
+        "Size of .cargo/registry and .cargo/git :-".bright_blue(),
+        convert(size as f64),
+        convert(budget as f64),
+        status
+    );
 }
 
 // query size of directory
 fn query_size(
     dir_path: &DirPath,
     (query_size_app, query_size_git, query_size_registry): (bool, bool, bool),
+    (cargo_config, registry_filter): (&CargoConfig, Option<&str>),
     crate_list: &CrateList,
     crate_detail: &CrateDetail,
+    report: &mut Option<Report>,
 ) {
-    let mut final_size = 0_u64;
     if query_size_app || query_size_git || query_size_registry {
+        let mut query = QueryReport::default();
         if query_size_app {
-            let bin_dir_size = get_size(dir_path.bin_dir()).unwrap_or(0_u64);
-            final_size += bin_dir_size;
-            println!(
-                "{:50} {:>10}",
-                format!(
-                    "Total size of {} .cargo/bin binary:",
-                    crate_list.installed_bin().len()
-                ),
-                convert(bin_dir_size as f64)
-            );
-            print_dash();
+            query.bin = Some(get_size(dir_path.bin_dir()).unwrap_or(0_u64));
+            query.trash = Some(get_size(dir_path.trash_dir()).unwrap_or(0_u64));
         }
         if query_size_app || query_size_git {
-            let git_dir_size = get_size(dir_path.git_dir()).unwrap_or(0_u64);
-            final_size += git_dir_size;
+            query.git = Some(get_size(dir_path.git_dir()).unwrap_or(0_u64));
+            query.git_checkout = Some(get_size(dir_path.checkout_dir()).unwrap_or(0_u64));
+            query.git_db = Some(get_size(dir_path.db_dir()).unwrap_or(0_u64));
+        }
+        if query_size_app || query_size_registry {
+            query.registry = Some(get_size(dir_path.registry_dir()).unwrap_or(0_u64));
+            query.registry_cache = Some(get_size(dir_path.cache_dir()).unwrap_or(0_u64));
+            query.registry_index = Some(get_size(dir_path.index_dir()).unwrap_or(0_u64));
+            query.registry_src = Some(get_size(dir_path.src_dir()).unwrap_or(0_u64));
+            query.registries = query_registry_size(dir_path, cargo_config, registry_filter);
+        }
+        query.total = query.bin.unwrap_or(0)
+            + query.git.unwrap_or(0)
+            + query.registry.unwrap_or(0)
+            + query.trash.unwrap_or(0);
+        if let Some(report) = report {
+            report.set_query(query);
+        } else {
+            show_query_size(dir_path, &query, crate_list, crate_detail);
+        }
+    }
+}
+
+// query size of each registry present in registry cache, index and src
+// directory which matches registry filter
+fn query_registry_size(
+    dir_path: &DirPath,
+    cargo_config: &CargoConfig,
+    registry_filter: Option<&str>,
+) -> Vec<RegistryQuery> {
+    let mut registries = BTreeSet::new();
+    for dir in [
+        dir_path.cache_dir(),
+        dir_path.index_dir(),
+        dir_path.src_dir(),
+    ] {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                registries.insert(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    registries
+        .into_iter()
+        .filter(|name| registry_filter.is_none_or(|filter| cargo_config.matches(name, filter)))
+        .map(|name| {
+            let registry_index = dir_path.index_dir().join(&name);
+            let registry_index = registry_index
+                .is_dir()
+                .then(|| RegistryIndex::new(&registry_index));
+            RegistryQuery {
+                url: cargo_config.url(&name).map(ToString::to_string),
+                index_kind: registry_index
+                    .as_ref()
+                    .map(|registry_index| registry_index.kind().to_string()),
+                index_crates: registry_index
+                    .as_ref()
+                    .map_or(0, |registry_index| registry_index.cache_entries().len()),
+                cache: get_size(dir_path.cache_dir().join(&name)).unwrap_or(0_u64),
+                index: get_size(dir_path.index_dir().join(&name)).unwrap_or(0_u64),
+                src: get_size(dir_path.src_dir().join(&name)).unwrap_or(0_u64),
+                name,
+            }
+        })
+        .collect()
+}
+
+// show queried size of directory
+fn show_query_size(
+    dir_path: &DirPath,
+    query: &QueryReport,
+    crate_list: &CrateList,
+    crate_detail: &CrateDetail,
+) {
+    if let Some(bin_dir_size) = query.bin {
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "Total size of {} .cargo/bin binary:",
+                crate_list.installed_bin().len()
+            ),
+            convert(bin_dir_size as f64)
+        );
+        print_dash();
+    }
+    if let Some(git_dir_size) = query.git {
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "Total size of {} .cargo/git crates:",
+                crate_list.installed_git().len()
+            ),
+            convert(git_dir_size as f64)
+        );
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "   \u{251c} Size of {} .cargo/git/checkout folder",
+                crate_detail.git_crates_archive().len()
+            ),
+            convert(query.git_checkout.unwrap_or(0) as f64)
+        );
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "   \u{2514} Size of {} .cargo/git/db folder",
+                crate_detail.git_crates_source().len()
+            ),
+            convert(query.git_db.unwrap_or(0) as f64)
+        );
+        print_dash();
+    }
+    if let Some(registry_dir_size) = query.registry {
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "Total size of {} .cargo/registry crates:",
+                crate_list.installed_registry().len()
+            ),
+            convert(registry_dir_size as f64)
+        );
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "   \u{251c} Size of {} .cargo/registry/cache folder",
+                crate_detail.registry_crates_archive().len()
+            ),
+            convert(query.registry_cache.unwrap_or(0) as f64)
+        );
+        println!(
+            "{:50} {:>10}",
+            "   \u{251c} Size of .cargo/registry/index folder",
+            convert(query.registry_index.unwrap_or(0) as f64)
+        );
+        println!(
+            "{:50} {:>10}",
+            format!(
+                "   \u{2514} Size of {} .cargo/git/src folder",
+                crate_detail.registry_crates_source().len()
+            ),
+            convert(query.registry_src.unwrap_or(0) as f64)
+        );
+        print_dash();
+        for registry in &query.registries {
+            let count = |size_detail: &HashMap<CrateId, u64>| {
+                size_detail
+                    .keys()
+                    .filter(|crate_id| crate_id.registry() == registry.name)
+                    .count()
+            };
             println!(
                 "{:50} {:>10}",
-                format!(
-                    "Total size of {} .cargo/git crates:",
-                    crate_list.installed_git().len()
-                ),
-                convert(git_dir_size as f64)
+                format!("Registry {}:", registry.name),
+                convert((registry.cache + registry.index + registry.src) as f64)
             );
+            if let Some(url) = &registry.url {
+                println!("   {url}");
+            }
             println!(
                 "{:50} {:>10}",
                 format!(
-                    "   \u{251c} Size of {} .cargo/git/checkout folder",
-                    crate_detail.git_crates_archive().len()
+                    "   \u{251c} Size of {} cache crates",
+                    count(crate_detail.registry_crates_archive())
                 ),
-                convert(get_size(dir_path.checkout_dir()).unwrap_or(0_u64) as f64)
+                convert(registry.cache as f64)
             );
             println!(
                 "{:50} {:>10}",
                 format!(
-                    "   \u{2514} Size of {} .cargo/git/db folder",
-                    crate_detail.git_crates_source().len()
+                    "   \u{251c} Size of {} {} index cache crates",
+                    registry.index_crates,
+                    registry.index_kind.as_deref().unwrap_or("no")
                 ),
-                convert(get_size(dir_path.db_dir()).unwrap_or(0_u64) as f64)
+                convert(registry.index as f64)
             );
-            print_dash();
-        }
-        if query_size_app || query_size_registry {
-            let registry_dir_size =
-                get_size(dir_path.registry_dir()).expect("failed to get size of registry dir");
-            final_size += registry_dir_size;
             println!(
                 "{:50} {:>10}",
                 format!(
-                    "Total size of {} .cargo/registry crates:",
-                    crate_list.installed_registry().len()
+                    "   \u{2514} Size of {} src crates",
+                    count(crate_detail.registry_crates_source())
                 ),
-                convert(registry_dir_size as f64)
+                convert(registry.src as f64)
             );
+            print_dash();
+        }
+    }
+    if let Some(trash_dir_size) = query.trash {
+        println!(
+            "{:50} {:>10}",
+            "Total size of .trim-trash quarantined path:",
+            convert(trash_dir_size as f64)
+        );
+        print_dash();
+    }
+    println!(
+        "{:50} {:>10}",
+        format!("Total size occupied by {}", dir_path.cargo_home().display()),
+        convert(query.total as f64)
+    );
+}
+
+// show path removed by removal plan along with reason of removal
+fn show_removed(removed: &[&PlanEntry], dry_run: bool, trash: bool) {
+    let mut size_cleaned = 0;
+    for entry in removed {
+        let reasons = entry
+            .reasons()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if dry_run {
             println!(
-                "{:50} {:>10}",
-                format!(
-                    "   \u{251c} Size of {} .cargo/registry/cache folder",
-                    crate_detail.registry_crates_archive().len()
-                ),
-                convert(get_size(dir_path.cache_dir()).unwrap_or(0_u64) as f64)
+                "{} {} {} ({})",
+                "Dry run:".yellow(),
+                "removed".red(),
+                entry.path().display(),
+                reasons
             );
+        } else if trash {
             println!(
-                "{:50} {:>10}",
-                "   \u{251c} Size of .cargo/registry/index folder",
-                convert(get_size(dir_path.index_dir()).unwrap_or(0_u64) as f64)
+                "{} {} ({})",
+                "Moved to trash".yellow(),
+                entry.path().display(),
+                reasons
             );
+        } else {
             println!(
-                "{:50} {:>10}",
-                format!(
-                    "   \u{2514} Size of {} .cargo/git/src folder",
-                    crate_detail.registry_crates_source().len()
-                ),
-                convert(get_size(dir_path.src_dir()).unwrap_or(0_u64) as f64)
+                "{} {} ({})",
+                "Removed".red(),
+                entry.path().display(),
+                reasons
             );
-            print_dash();
         }
+        size_cleaned += entry.size();
+    }
+    // space of crates moved to trash is only freed once trash is purged
+    let action = if trash { "moved to trash" } else { "removed" };
+    println!(
+        "{}",
+        format!(
+            "Total size of crates {action} :- {:.3} MB",
+            size_cleaned as f64 / 1000_f64.powf(2.0)
+        )
+        .bright_blue()
+    );
+}
+
+// List out previous run recorded in history file along with cumulative space
+// reclaimed by them
+fn history_subcommand(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    report: &mut Option<Report>,
+    errors: &mut Vec<TrimError>,
+) -> Result<(), TrimError> {
+    if let Some(matches) = app.subcommand_matches("history") {
+        let since = matches
+            .value_of("since")
+            .map(|value| cargo_trim::parse_duration(value, "--since"))
+            .transpose()?;
+        let last = matches
+            .value_of("last")
+            .map(|value| {
+                value.parse::<usize>().map_err(|_| TrimError::InvalidValue {
+                    argument: "--last",
+                    value: value.to_string(),
+                })
+            })
+            .transpose()?;
+        let now = SystemTime::now();
+        let mut entries = History::new(dir_path.history_file())
+            .entries(errors)?
+            .into_iter()
+            .filter(|entry| {
+                since.is_none_or(|since| {
+                    now.duration_since(entry.timestamp()).unwrap_or_default() <= since
+                })
+            })
+            .filter(|entry| {
+                matches
+                    .value_of("crate")
+                    .is_none_or(|name| entry.removed_crate(name))
+            })
+            .collect::<Vec<_>>();
+        if let Some(last) = last {
+            entries.drain(..entries.len().saturating_sub(last));
+        }
+        if let Some(report) = report {
+            report.set_history(&entries);
+            return Ok(());
+        }
+        if !matches.is_present("summary") {
+            for entry in &entries {
+                show_history_entry(entry);
+            }
+        }
+        show_history_summary(&HistorySummary::new(&entries));
+    }
+    Ok(())
+}
+
+// show single run of history along with size freed for each category
+fn show_history_entry(entry: &HistoryEntry) {
+    println!(
+        "{} {}",
+        format_timestamp(entry.timestamp()).bold(),
+        format!("cargo {}", entry.args().join(" ")).bright_blue()
+    );
+    let freed = entry.freed();
+    for (index, (category, size)) in freed.iter().enumerate() {
+        let branch = if index + 1 == freed.len() {
+            "\u{2514}"
+        } else {
+            "\u{251c}"
+        };
         println!(
             "{:50} {:>10}",
+            format!("   {branch} {category}"),
+            convert(*size as f64)
+        );
+    }
+    let action = match entry.trash_id() {
+        Some(trash_id) => format!(
+            "Moved {} path to trash run {trash_id}:",
+            entry.removed().len()
+        ),
+        None => format!("Freed {} path:", entry.removed().len()),
+    };
+    println!("{:50} {:>10}", action, convert(entry.total() as f64));
+    print_dash();
+}
+
+// show cumulative space reclaimed by run
+fn show_history_summary(summary: &HistorySummary) {
+    println!(
+        "{:50} {:>10}",
+        format!("Total space reclaimed by {} run:", summary.runs()),
+        convert(summary.freed() as f64)
+    );
+    let categories = summary.categories();
+    for (index, (category, size)) in categories.iter().enumerate() {
+        let branch = if index + 1 == categories.len() {
+            "\u{2514}"
+        } else {
+            "\u{251c}"
+        };
+        println!(
+            "{:50} {:>10}",
+            format!("   {branch} {category}"),
+            convert(*size as f64)
+        );
+    }
+    println!(
+        "{:50} {:>10}",
+        "Total space moved to trash:",
+        convert(summary.quarantined() as f64)
+    );
+}
+
+// format time as utc date and time such as `2024-01-31 10:20:30 UTC`
+fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    // convert days since unix epoch to civil date
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_position = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_position + 2) / 5 + 1;
+    let month = if month_position < 10 {
+        month_position + 3
+    } else {
+        month_position - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// show summary of path moved to trash by current run
+fn show_trash_run(trash_run: &TrashRun) {
+    println!(
+        "{}",
+        format!(
+            "Moved {} path of size {} to trash, restore them with `cargo trim undo {}`",
+            trash_run.entries().len(),
+            convert(trash_run.size() as f64),
+            trash_run.id()
+        )
+        .bright_blue()
+    );
+}
+
+// Restore path of trash run to their original location
+fn undo_subcommand(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    (package_cache_lock, wait): (&mut PackageCacheLock, bool),
+    (report, errors): (Option<&Report>, &mut Vec<TrimError>),
+) -> Result<(), TrimError> {
+    if let Some(matches) = app.subcommand_matches("undo") {
+        let id = matches.value_of("id");
+        let Some(trash_run) = Trash::new(dir_path).run(id)? else {
+            if let Some(id) = id {
+                return Err(TrimError::InvalidValue {
+                    argument: "<id>",
+                    value: id.to_string(),
+                });
+            }
+            show!(report, "{}", "Trash is empty".bright_blue());
+            return Ok(());
+        };
+        acquire_lock(package_cache_lock, wait)?;
+        let id = trash_run.id().to_string();
+        let restored = trash_run.restore(errors);
+        for entry in &restored {
+            show!(
+                report,
+                "{} {}",
+                "Restored".green(),
+                entry.original().display()
+            );
+        }
+        let size = restored.iter().map(TrashEntry::size).sum::<u64>();
+        show!(
+            report,
+            "{}",
             format!(
-                "Total size occupied by {}",
-                std::env::var("CARGO_HOME").expect("No environmental variable CARGO_HOME present")
-            ),
-            convert(final_size as f64)
+                "Restored {} path of size {} from trash run {id}",
+                restored.len(),
+                convert(size as f64)
+            )
+            .bright_blue()
         );
     }
+    Ok(())
+}
+
+// List out and purge trash run
+fn trash_subcommand(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    report: Option<&Report>,
+) -> Result<(), TrimError> {
+    if let Some(matches) = app.subcommand_matches("trash") {
+        let trash = Trash::new(dir_path);
+        if matches.is_present("list") {
+            let runs = trash.runs()?;
+            if runs.is_empty() {
+                show!(report, "{}", "Trash is empty".bright_blue());
+            }
+            for trash_run in &runs {
+                let age = SystemTime::now()
+                    .duration_since(trash_run.created())
+                    .unwrap_or_default();
+                show!(
+                    report,
+                    "{:20} {:>6} path {:>12}   {} hour ago",
+                    trash_run.id(),
+                    trash_run.entries().len(),
+                    convert(trash_run.size() as f64),
+                    age.as_secs() / 3600
+                );
+            }
+        }
+        if let Some(purge) = matches.subcommand_matches("purge") {
+            let older_than = purge
+                .value_of("older than")
+                .map(|value| cargo_trim::parse_duration(value, "--older-than"))
+                .transpose()?;
+            let purged = trash.purge(older_than)?;
+            for trash_run in &purged {
+                show!(report, "{} trash run {}", "Purged".red(), trash_run.id());
+            }
+            let size = purged.iter().map(TrashRun::size).sum::<u64>();
+            show!(
+                report,
+                "{}",
+                format!("Freed {} by purging trash", convert(size as f64)).bright_blue()
+            );
+        }
+    }
+    Ok(())
 }
 
 // Perform query about config file data
-fn config_subcommand(app: &ArgMatches, config_file: &ConfigFile) {
+fn config_subcommand(app: &ArgMatches, config_file: &ConfigFile, report: Option<&Report>) {
     if app.is_present("config") {
         let matches = app.subcommand_matches("config").unwrap();
         let read_include = config_file.include();
@@ -609,139 +1828,163 @@ fn config_subcommand(app: &ArgMatches, config_file: &ConfigFile) {
         let read_directory = config_file.directory();
         if matches.is_present("directory") {
             for name in read_directory {
-                println!("{}", name);
+                show!(report, "{name}");
             }
         }
         if matches.is_present("include") {
             for name in read_include {
-                println!("{}", name);
+                show!(report, "{name}");
             }
         }
         if matches.is_present("exclude") {
             for name in read_exclude {
-                println!("{}", name);
+                show!(report, "{name}");
             }
         }
+        if matches.is_present("mode") {
+            show!(report, "{}", config_file.mode());
+        }
     }
 }
 
+// show which include and exclude rule matches crate and whether crate can be
+// removed under policy mode, crate name with version is also matched against
+// rule with semver requirement
+fn test_crate_filter(crate_filter: &CrateFilter, full_name: &str, report: Option<&Report>) {
+    // simple name is name of crate without its version or git revision
+    let crate_id = CrateId::parse(full_name, "");
+    let version = crate_id.as_ref().map(CrateId::version);
+    let simple_name = crate_id.as_ref().map_or_else(
+        || {
+            full_name
+                .rsplit_once('-')
+                .filter(|(name, rev)| {
+                    !name.is_empty()
+                        && (*rev == "HEAD"
+                            || (rev.len() >= 7 && rev.chars().all(|c| c.is_ascii_hexdigit())))
+                })
+                .map_or(full_name, |(name, _)| name)
+        },
+        CrateId::name,
+    );
+    show!(
+        report,
+        "Testing {full_name} (name: {simple_name}, mode: {})",
+        crate_filter.mode()
+    );
+    let rule_types = [
+        ("include", crate_filter.include()),
+        ("exclude", crate_filter.exclude()),
+    ];
+    for (rule_type, rules) in &rule_types {
+        for rule in *rules {
+            if let Some(name) = rule.matches(full_name, simple_name, version) {
+                show!(
+                    report,
+                    "  \u{251c} {rule_type} rule {:?} ({} from {}) matches {name}",
+                    rule.value(),
+                    rule.kind(),
+                    rule.source()
+                );
+            }
+        }
+    }
+    let included_by = crate_filter.included_by(full_name, simple_name, version);
+    let excluded_by = crate_filter.excluded_by(full_name, simple_name, version);
+    let flags = "--all, --old-clean, --orphan-clean, --max-age and --budget";
+    let result = match (included_by, excluded_by, crate_filter.mode()) {
+        (Some(include), Some(exclude), _) => format!(
+            "kept by {flags} since exclude rule {:?} takes priority over include rule {:?}",
+            exclude.value(),
+            include.value()
+        ),
+        (None, Some(exclude), _) => {
+            format!(
+                "kept by {flags} since it matches exclude rule {:?}",
+                exclude.value()
+            )
+        }
+        (Some(include), None, _) => format!(
+            "removable by {flags} since it matches include rule {:?}",
+            include.value()
+        ),
+        (None, None, FilterMode::Allowlist) => {
+            format!("kept by {flags} since allowlist mode only removes included crates")
+        }
+        (None, None, FilterMode::Denylist) => {
+            format!(
+                "removable by {flags} since denylist mode removes all crates which are not \
+                 excluded"
+            )
+        }
+    };
+    show!(report, "  \u{2514} {full_name} is {result}");
+}
+
 // force remove all crates
 fn force_remove(
     dir_path: &DirPath,
     (force_remove_app, force_remove_git, force_remove_registry): (bool, bool, bool),
     (dry_run_app, dry_run_git, dry_run_registry): (bool, bool, bool),
+    trash_run: &mut Option<TrashRun>,
+    history: &mut HistoryEntry,
+    report: &mut Option<Report>,
+    errors: &mut Vec<TrimError>,
 ) {
     if force_remove_app || force_remove_git || force_remove_registry {
         if force_remove_app || force_remove_registry {
             let dry_run = dry_run_app || dry_run_registry;
-            delete_folder(&dir_path.cache_dir(), dry_run);
-            delete_folder(&dir_path.src_dir(), dry_run);
+            remove_folder(
+                (dir_path.cache_dir(), "registry archive"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
+            remove_folder(
+                (dir_path.src_dir(), "registry source"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
             // Delete out .cache folder also
-            let index_path = dir_path.index_dir();
-            for entry in fs::read_dir(index_path)
-                .expect("Failed to read index directory during force remove")
-            {
-                let entry = entry.unwrap().path();
-                let registry_dir = entry.as_path();
-                for folder in fs::read_dir(registry_dir)
-                    .expect("Failed to read registry directory in force remove")
-                {
-                    let folder = folder.unwrap().path();
-                    let folder_name = folder.file_name().unwrap();
-                    if folder_name == ".cache" {
-                        delete_folder(&folder, dry_run);
-                    }
-                }
-            }
+            remove_index_cache(
+                dir_path.index_dir(),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
         }
         if force_remove_app || force_remove_git {
             let dry_run = dry_run_app || dry_run_git;
-            delete_folder(&dir_path.checkout_dir(), dry_run);
-            delete_folder(&dir_path.db_dir(), dry_run);
-        }
-        println!("{}", "Successfully removed all crates".red());
-    }
-}
-
-// remove all crates by following config file information
-fn remove_all(
-    list_crate: &CrateList,
-    config_file: &ConfigFile,
-    registry_crates_location: &mut RegistryDir,
-    git_crates_location: &GitDir,
-    (all_app, all_git, all_registry): (bool, bool, bool),
-    crate_detail: &CrateDetail,
-) {
-    if all_app || all_git || all_registry {
-        let mut total_size_cleaned = 0.0;
-        if all_app || all_registry {
-            for crate_name in list_crate.installed_registry() {
-                total_size_cleaned +=
-                    registry_crates_location.remove_all(&config_file, crate_name, crate_detail);
-            }
-        }
-        if all_app || all_git {
-            for crate_name in list_crate.installed_git() {
-                total_size_cleaned +=
-                    git_crates_location.remove_all(&config_file, crate_name, crate_detail);
-            }
-        }
-        println!(
-            "{}",
-            format!(
-                "Total size of crates removed :- {:.3} MB",
-                total_size_cleaned
-            )
-            .bright_blue()
-        );
-    }
-}
-
-// Remove certain crates
-fn remove_crate(
-    list_crate: &CrateList,
-    (app, git_subcommand, registry_subcommand): (&ArgMatches, &ArgMatches, &ArgMatches),
-    registry_crates_location: &mut RegistryDir,
-    git_crates_location: &GitDir,
-    crate_detail: &CrateDetail,
-) {
-    let remove_crate_app = app.is_present("remove-crate");
-    let remove_crate_git = git_subcommand.is_present("remove-crate");
-    let remove_crate_registry = registry_subcommand.is_present("remove-crate");
-    if remove_crate_app || remove_crate_git || remove_crate_registry {
-        let mut size_cleaned = 0.0;
-        let value = app.value_of("remove-crate").unwrap_or_else(|| {
-            git_subcommand
-                .value_of("remove-crate")
-                .unwrap_or_else(|| registry_subcommand.value_of("remove-crate").unwrap())
-        });
-        if list_crate.installed_registry().contains(&value.to_string())
-            && (remove_crate_app || remove_crate_registry)
-        {
-            registry_crates_location.remove_crate(value);
-            size_cleaned += crate_detail.find_size_registry_all(value);
-        }
-
-        if list_crate.installed_git().contains(&value.to_string())
-            && (remove_crate_app || remove_crate_git)
-        {
-            git_crates_location.remove_crate(value);
-            size_cleaned += crate_detail.find_size_git_all(value);
+            remove_folder(
+                (dir_path.checkout_dir(), "git checkout"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
+            remove_folder(
+                (dir_path.db_dir(), "git db"),
+                dry_run,
+                trash_run,
+                (history, report),
+                errors,
+            );
         }
-        println!(
-            "{}",
-            format!("Total size removed :- {:.3} MB", size_cleaned).bright_blue()
-        );
+        show!(report, "{}", "Successfully removed all crates".red());
     }
 }
 
 // show out top n crates
 fn top_crates(
     app: &ArgMatches,
-    git_subcommand: &ArgMatches,
-    registry_subcommand: &ArgMatches,
+    (git_subcommand, registry_subcommand): (&ArgMatches, &ArgMatches),
     crate_detail: &CrateDetail,
-) {
+    report: &mut Option<Report>,
+) -> Result<(), TrimError> {
     let top_app = app.is_present("top crates");
     let top_git = git_subcommand.is_present("top crates");
     let top_registry = registry_subcommand.is_present("top crates");
@@ -754,44 +1997,70 @@ fn top_crates(
 
         let number = value
             .parse::<usize>()
-            .expect("Cannot convert top n crates value in usize");
+            .map_err(|_| TrimError::InvalidValue {
+                argument: "--top",
+                value: value.to_string(),
+            })?;
         if top_app {
-            show_top_number_crates(crate_detail, "bin", number);
+            show_top_number_crates(crate_detail, "bin", number, report);
         }
         if top_app || top_git {
-            show_top_number_crates(crate_detail, "git_archive", number);
-            show_top_number_crates(crate_detail, "git_source", number);
+            show_top_number_crates(crate_detail, "git_archive", number, report);
+            show_top_number_crates(crate_detail, "git_source", number, report);
         }
         if top_app || top_registry {
-            show_top_number_crates(crate_detail, "registry_archive", number);
-            show_top_number_crates(crate_detail, "registry_source", number);
+            show_top_number_crates(crate_detail, "registry_archive", number, report);
+            show_top_number_crates(crate_detail, "registry_source", number, report);
         }
     }
+    Ok(())
 }
 
 // top_crates() help to list out top n crates
-fn show_top_number_crates(crate_detail: &CrateDetail, crate_type: &str, number: usize) {
-    let blank_hashmap = HashMap::new();
-    let size_detail = match crate_type {
-        "bin" => crate_detail.bin(),
-        "git_archive" => crate_detail.git_crates_archive(),
-        "git_source" => crate_detail.git_crates_source(),
-        "registry_archive" => crate_detail.registry_crates_archive(),
-        "registry_source" => crate_detail.registry_crates_source(),
-        _ => &blank_hashmap,
+fn show_top_number_crates(
+    crate_detail: &CrateDetail,
+    crate_type: &str,
+    number: usize,
+    report: &mut Option<Report>,
+) {
+    let named_size = |size_detail: &HashMap<String, u64>| {
+        size_detail
+            .iter()
+            .map(|(name, size)| (name.clone(), *size))
+            .collect::<Vec<_>>()
+    };
+    let registry_size = |size_detail: &HashMap<CrateId, u64>| {
+        size_detail
+            .iter()
+            .map(|(crate_id, size)| (crate_id.to_string(), *size))
+            .collect::<Vec<_>>()
     };
-    let mut vector = size_detail.iter().collect::<Vec<_>>();
-    vector.sort_by(|a, b| (b.1).cmp(a.1));
-    let title = format!("Top {} {}", number, crate_type);
+    let git_size = |size_detail: &HashMap<GitId, u64>| {
+        size_detail
+            .iter()
+            .map(|(git_id, size)| (git_id.to_string(), *size))
+            .collect::<Vec<_>>()
+    };
+    let mut vector = match crate_type {
+        "bin" => named_size(crate_detail.bin()),
+        "git_archive" => git_size(crate_detail.git_crates_archive()),
+        "git_source" => git_size(crate_detail.git_crates_source()),
+        "registry_archive" => registry_size(crate_detail.registry_crates_archive()),
+        "registry_source" => registry_size(crate_detail.registry_crates_source()),
+        _ => Vec::new(),
+    };
+    vector.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    vector.truncate(number);
+    if let Some(report) = report {
+        report.add_top(crate_type, &vector);
+        return;
+    }
+    let title = format!("Top {number} {crate_type}");
     show_title(title.as_str());
     if vector.is_empty() {
         println!("|{:^40}|{:^10}|", "NONE".red(), "0.000".red());
-    } else if vector.len() < number {
-        for i in 0..vector.len() {
-            print_index_value_crate(&vector, i);
-        }
     } else {
-        for i in 0..number {
+        for i in 0..vector.len() {
             print_index_value_crate(&vector, i);
         }
     }
@@ -799,72 +2068,68 @@ fn show_top_number_crates(crate_detail: &CrateDetail, crate_type: &str, number:
 }
 
 // print crate name
-fn print_index_value_crate(vector: &[(&String, &u64)], i: usize) {
-    let crate_name = vector[i].0;
+fn print_index_value_crate(vector: &[(String, u64)], i: usize) {
+    let crate_name = &vector[i].0;
     let size = vector[i].1;
-    let size = (*size as f64) / 1024_f64.powf(2.0);
-    println!("|{:^40}|{:^10.3}|", crate_name, size);
+    let size = (size as f64) / 1024_f64.powf(2.0);
+    println!("|{crate_name:^40}|{size:^10.3}|");
 }
 
 // Update cargo lock
-fn update_cargo_toml(app: &ArgMatches, cargo_toml_location: &[PathBuf]) {
+fn update_cargo_toml(
+    app: &ArgMatches,
+    cargo_toml_location: &[PathBuf],
+    report: Option<&Report>,
+    errors: &mut Vec<TrimError>,
+) {
     if app.is_present("update") {
         for location in cargo_toml_location {
-            let mut cargo_lock = location.clone();
-            cargo_lock.push("Cargo.lock");
-            // at first try generating lock file
-            if !cargo_lock.exists() {
-                if let Err(e) = Command::new("cargo")
-                    .arg("generate-lockfile")
-                    .current_dir(location)
-                    .output()
-                {
-                    panic!(format!("Failed to generate Cargo.lock {}", e));
-                }
-            }
-            // helps so we may not need to generate lock file again for workspace project
-            if cargo_lock.exists() {
-                let message = format!("Updating {}", cargo_lock.to_str().unwrap().bright_blue());
-                println!("{}", message);
-                if let Err(e) = Command::new("cargo")
-                    .arg("update")
-                    .current_dir(location)
-                    .output()
-                {
-                    panic!(format!("Failed to update Cargo.lock {}", e));
-                }
-            }
+            show!(
+                report,
+                "Updating {}",
+                location
+                    .join("Cargo.lock")
+                    .display()
+                    .to_string()
+                    .bright_blue()
+            );
+            collect_error(cargo_trim::update_cargo_lock(location), errors);
         }
-        println!("{}", "Successfully update all Cargo.lock".bright_blue());
+        show!(
+            report,
+            "{}",
+            "Successfully update all Cargo.lock".bright_blue()
+        );
     }
 }
 
 // Wipe certain directory
-fn wipe_directory(app: &ArgMatches, dir_path: &DirPath) {
+fn wipe_directory(
+    app: &ArgMatches,
+    dir_path: &DirPath,
+    trash_run: &mut Option<TrashRun>,
+    (history, report): (&mut HistoryEntry, &mut Option<Report>),
+) -> Result<(), TrimError> {
     if app.is_present("wipe") {
         let value = app.value_of("wipe").unwrap();
         let dry_run = app.is_present("dry run");
-        match value {
-            "git" => delete_folder(&dir_path.git_dir(), dry_run),
-            "checkouts" => delete_folder(&dir_path.checkout_dir(), dry_run),
-            "db" => delete_folder(&dir_path.db_dir(), dry_run),
-            "registry" => delete_folder(&dir_path.registry_dir(), dry_run),
-            "cache" => delete_folder(&dir_path.cache_dir(), dry_run),
-            "index" => delete_folder(&dir_path.index_dir(), dry_run),
-            "src" => delete_folder(&dir_path.src_dir(), dry_run),
-            _ => (),
-        }
-    }
-}
-
-// delete folder with folder path provided
-fn delete_folder(path: &PathBuf, dry_run: bool) {
-    if path.exists() {
-        if dry_run {
-            println!("{} {} {:?}", "Dry run:".yellow(), "removed".red(), path);
-        } else {
-            fs::remove_dir_all(path).expect("failed to remove all directory content");
-            println!("{} {:?}", "Removed".red(), path);
+        let (folder, category) = match value {
+            "git" => (dir_path.git_dir(), "git"),
+            "checkouts" => (dir_path.checkout_dir(), "git checkout"),
+            "db" => (dir_path.db_dir(), "git db"),
+            "registry" => (dir_path.registry_dir(), "registry"),
+            "cache" => (dir_path.cache_dir(), "registry archive"),
+            "index" => (dir_path.index_dir(), "registry index"),
+            "src" => (dir_path.src_dir(), "registry source"),
+            _ => return Ok(()),
+        };
+        if let Some(size) = cargo_trim::delete_folder(folder, dry_run, trash_run.as_mut())? {
+            record_folder(
+                (folder, category, size),
+                (dry_run, trash_id(trash_run.as_ref(), dry_run)),
+                (history, report),
+            );
         }
     }
+    Ok(())
 }
@@ -0,0 +1,81 @@
+use crate::error::{io_error, TrimError};
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Lock of `.package-cache` file present in cargo home. Cargo holds same lock
+/// while downloading, extracting or checking out crates so holding it makes
+/// sure directory is not removed while cargo is writing into it. Lock is
+/// released when value is dropped
+pub struct PackageCacheLock {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl PackageCacheLock {
+    /// create new `PackageCacheLock` for cargo home, lock is not acquired till
+    /// `acquire` is called
+    #[must_use]
+    pub fn new(cargo_home: &Path) -> Self {
+        Self {
+            path: cargo_home.join(".package-cache"),
+            file: None,
+        }
+    }
+
+    /// acquire lock if it is not already acquired. When other process holds
+    /// lock either block till it is released or return error if wait is false.
+    /// Cargo home which doesn't exist has nothing to protect and file system
+    /// which doesn't support lock is used without lock like cargo does
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if lock file cannot be opened or locked, or if lock is
+    /// held by other process and wait is false
+    pub fn acquire(&mut self, wait: bool) -> Result<(), TrimError> {
+        if self.file.is_some() {
+            return Ok(());
+        }
+        if !self.path.parent().is_some_and(Path::exists) {
+            return Ok(());
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .map_err(io_error(&self.path, "open package cache lock"))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                if !wait {
+                    return Err(TrimError::Locked {
+                        path: self.path.clone(),
+                    });
+                }
+                file.lock()
+                    .map_err(io_error(&self.path, "lock package cache"))?;
+            }
+            Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => {}
+            Err(TryLockError::Error(e)) => {
+                return Err(io_error(&self.path, "lock package cache")(e));
+            }
+        }
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// release lock so other process such as cargo can acquire it
+    pub fn release(&mut self) {
+        self.file = None;
+    }
+
+    /// return true if lock is currently held
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.file.is_some()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::error::{io_error, TrimError};
+use serde_derive::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// name of project local config file
+const PROJECT_CONFIG_FILE: &str = ".cargo-trim.toml";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ProjectConfigData {
+    protect: Vec<String>,
+    skip: Vec<String>,
+}
+
+/// Project local `.cargo-trim.toml` config file present inside directory of
+/// rust project. It lists crates which are protected from removal same as
+/// exclude rule and subtrees which are skipped while scanning for Cargo.toml
+pub struct ProjectConfig {
+    path: PathBuf,
+    protect: Vec<String>,
+    skip: Vec<String>,
+    skip_pattern: Vec<glob::Pattern>,
+}
+
+impl ProjectConfig {
+    /// read project config file present in directory, return none if
+    /// directory doesn't contain project config file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if project config file cannot be read or parsed or its
+    /// skip pattern is invalid
+    pub fn read(directory: &Path) -> Result<Option<Self>, TrimError> {
+        let path = directory.join(PROJECT_CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let buffer = fs::read_to_string(&path).map_err(io_error(&path, "read project config"))?;
+        let data =
+            toml::from_str::<ProjectConfigData>(&buffer).map_err(|source| TrimError::Toml {
+                path: path.clone(),
+                source,
+            })?;
+        let mut skip_pattern = Vec::new();
+        for value in &data.skip {
+            skip_pattern.push(
+                glob::Pattern::new(value).map_err(|_| TrimError::InvalidValue {
+                    argument: "skip pattern",
+                    value: value.clone(),
+                })?,
+            );
+        }
+        Ok(Some(Self {
+            path,
+            protect: data.protect,
+            skip: data.skip,
+            skip_pattern,
+        }))
+    }
+
+    /// return path of project config file
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// return crates protected by project, value uses same syntax as exclude
+    /// rule
+    #[must_use]
+    pub fn protect(&self) -> &[String] {
+        &self.protect
+    }
+
+    /// return glob pattern of subtrees skipped while scanning for Cargo.toml,
+    /// pattern is relative to directory of project config file
+    #[must_use]
+    pub fn skip(&self) -> &[String] {
+        &self.skip
+    }
+
+    /// check if path is inside directory of project config file and matches
+    /// any skip pattern
+    #[must_use]
+    pub fn is_skipped(&self, path: &Path) -> bool {
+        let Some(directory) = self.path.parent() else {
+            return false;
+        };
+        path.strip_prefix(directory).is_ok_and(|relative| {
+            self.skip_pattern
+                .iter()
+                .any(|pattern| pattern.matches_path(relative))
+        })
+    }
+}
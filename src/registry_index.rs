@@ -0,0 +1,140 @@
+use crate::{
+    error::TrimError,
+    utils::{file_name, read_dir},
+};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Protocol used by registry index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexKind {
+    /// index is git clone of index repository
+    Git,
+    /// index is fetched over http using sparse protocol and only cache of
+    /// fetched crate is stored
+    Sparse,
+}
+
+impl fmt::Display for IndexKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Git => "git",
+            Self::Sparse => "sparse",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// Registry index present inside registry index directory
+pub struct RegistryIndex {
+    path: PathBuf,
+    kind: IndexKind,
+}
+
+impl RegistryIndex {
+    /// detect kind of registry index present at path, index which contains git
+    /// repository is git index and other is sparse index
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        let kind = if path.join(".git").is_dir() {
+            IndexKind::Git
+        } else {
+            IndexKind::Sparse
+        };
+        Self {
+            path: path.to_path_buf(),
+            kind,
+        }
+    }
+
+    /// list out all registry index present inside index directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if index directory cannot be read
+    pub fn list(index_dir: &Path) -> Result<Vec<Self>, TrimError> {
+        if !index_dir.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(read_dir(index_dir)?
+            .iter()
+            .filter(|path| path.is_dir())
+            .map(|path| Self::new(path))
+            .collect())
+    }
+
+    /// return path of registry index
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// return name of registry directory
+    #[must_use]
+    pub fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    /// return kind of registry index
+    #[must_use]
+    pub fn kind(&self) -> IndexKind {
+        self.kind
+    }
+
+    /// return path of `.cache` directory which stores index entry of crates
+    #[must_use]
+    pub fn cache_dir(&self) -> PathBuf {
+        self.path.join(".cache")
+    }
+
+    /// return path of index cache entry of crate. Both git and sparse index
+    /// store entry at lowercase name of crate inside directory made from its
+    /// prefix
+    #[must_use]
+    pub fn cache_path(&self, crate_name: &str) -> PathBuf {
+        let name = crate_name.to_lowercase();
+        let mut path = self.cache_dir();
+        match name.len() {
+            1 => path.push("1"),
+            2 => path.push("2"),
+            3 => {
+                path.push("3");
+                path.push(&name[..1]);
+            }
+            _ => {
+                path.push(&name[..2]);
+                path.push(&name[2..4]);
+            }
+        }
+        path.push(name);
+        path
+    }
+
+    /// list out name of crate along with path and size of its index cache
+    /// entry, `config.json` of sparse index is not an entry of crate
+    #[must_use]
+    pub fn cache_entries(&self) -> Vec<(String, PathBuf, u64)> {
+        let mut entries = Vec::new();
+        collect_cache_entries(&self.cache_dir(), 0, &mut entries);
+        entries.sort();
+        entries
+    }
+}
+
+// collect index cache entry present inside prefix directory, entry is always
+// present at depth of 2 or 3 from cache directory
+fn collect_cache_entries(path: &Path, depth: usize, entries: &mut Vec<(String, PathBuf, u64)>) {
+    let Ok(paths) = read_dir(path) else {
+        return;
+    };
+    for path in paths {
+        if path.is_dir() {
+            collect_cache_entries(&path, depth + 1, entries);
+        } else if depth > 0 {
+            let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+            entries.push((file_name(&path), path, size));
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use crate::{
+    crate_filter::CrateFilter,
+    crate_id::CrateId,
+    error::{io_error, TrimError},
+    git_id::GitId,
+    registry_index::RegistryIndex,
+    trash::TrashRun,
+    utils::read_dir,
+    CrateDetail, CrateList, DirPath,
+};
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt, fs,
+    hash::Hash,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Reason for which path is added to removal plan
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Reason {
+    /// crate is older version of other installed crate
+    Old,
+    /// crate is not used by any project present in config directory
+    Orphan,
+    /// crate is removed by following include and exclude rules
+    All,
+    /// crate is explicitly requested to be removed
+    Requested,
+    /// crate is not used for more than max age
+    Stale,
+    /// crate is least recently used and cache is over budget
+    Budget,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Old => "old",
+            Self::Orphan => "orphan",
+            Self::All => "all",
+            Self::Requested => "requested",
+            Self::Stale => "stale",
+            Self::Budget => "budget",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// Type of path present in removal plan
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntryKind {
+    /// `.crate` archive present inside registry cache directory
+    RegistryArchive,
+    /// extracted crate source present inside registry src directory
+    RegistrySource,
+    /// crate entry present inside registry index `.cache` directory
+    IndexCache,
+    /// checkout of git repo at certain revision
+    GitCheckout,
+    /// bare git repo database
+    GitDb,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::RegistryArchive => "registry archive",
+            Self::RegistrySource => "registry source",
+            Self::IndexCache => "index cache",
+            Self::GitCheckout => "git checkout",
+            Self::GitDb => "git db",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// Single path which is removed on execution of removal plan
+pub struct PlanEntry {
+    path: PathBuf,
+    kind: EntryKind,
+    crate_name: String,
+    size: u64,
+    reasons: BTreeSet<Reason>,
+}
+
+impl PlanEntry {
+    /// return path which will be removed
+    #[must_use]
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// return type of path
+    #[must_use]
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// return name of crate to which path belongs
+    #[must_use]
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+
+    /// return size of path in bytes
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// return all reasons for which path is present in plan
+    #[must_use]
+    pub fn reasons(&self) -> &BTreeSet<Reason> {
+        &self.reasons
+    }
+}
+
+/// Deduplicated set of path to be removed from cargo home, plan is first
+/// created from `CrateList` and `CrateDetail` and then executed at once so
+/// same path is never removed or counted twice
+pub struct RemovalPlan<'a> {
+    crate_list: &'a CrateList,
+    crate_detail: &'a CrateDetail,
+    registry_cache_dir: PathBuf,
+    registry_src_dir: PathBuf,
+    registry_index: Vec<RegistryIndex>,
+    checkout_dir: PathBuf,
+    db_dir: PathBuf,
+    planned_registry: BTreeSet<CrateId>,
+    entries: BTreeMap<PathBuf, PlanEntry>,
+}
+
+impl<'a> RemovalPlan<'a> {
+    /// create new empty `RemovalPlan`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if registry index directory cannot be read
+    pub fn new(
+        dir_path: &DirPath,
+        crate_list: &'a CrateList,
+        crate_detail: &'a CrateDetail,
+    ) -> Result<Self, TrimError> {
+        Ok(Self {
+            crate_list,
+            crate_detail,
+            registry_cache_dir: dir_path.cache_dir().clone(),
+            registry_src_dir: dir_path.src_dir().clone(),
+            registry_index: RegistryIndex::list(dir_path.index_dir())?,
+            checkout_dir: dir_path.checkout_dir().clone(),
+            db_dir: dir_path.db_dir().clone(),
+            planned_registry: BTreeSet::new(),
+            entries: BTreeMap::new(),
+        })
+    }
+
+    /// keep only registry index of registry directory for which filter returns
+    /// true, should be used along with `CrateList::filter_registry`
+    pub fn filter_registry<F>(&mut self, filter: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.registry_index
+            .retain(|registry_index| filter(&registry_index.name()));
+    }
+
+    /// add all path of installed registry crate to plan, index cache entry is
+    /// only added once all installed version of crate from its registry are part
+    /// of plan
+    pub fn add_registry_crate(&mut self, crate_id: &CrateId, reason: Reason) {
+        if !self.crate_list.installed_registry().contains(crate_id) {
+            return;
+        }
+        let crate_name = crate_id.to_string();
+        let archive_size = size_of(self.crate_detail.registry_crates_archive(), crate_id);
+        let path = self
+            .registry_cache_dir
+            .join(crate_id.registry())
+            .join(format!("{crate_name}.crate"));
+        if path.is_file() {
+            self.add_entry(
+                path,
+                EntryKind::RegistryArchive,
+                &crate_name,
+                archive_size,
+                reason,
+            );
+        }
+        self.add_registry_source(crate_id, reason);
+        self.planned_registry.insert(crate_id.clone());
+
+        // index cache is shared by all version of crate so only remove it when no
+        // version of crate remains after plan is executed
+        let all_version_planned = self
+            .crate_list
+            .installed_registry()
+            .iter()
+            .filter(|installed| {
+                installed.name() == crate_id.name() && installed.registry() == crate_id.registry()
+            })
+            .all(|installed| self.planned_registry.contains(installed));
+        if all_version_planned {
+            let index_cache_path = self
+                .registry_index
+                .iter()
+                .filter(|registry_index| registry_index.name() == crate_id.registry())
+                .map(|registry_index| registry_index.cache_path(crate_id.name()))
+                .collect::<Vec<_>>();
+            for path in index_cache_path {
+                if path.is_file() {
+                    let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+                    self.add_entry(path, EntryKind::IndexCache, &crate_name, size, reason);
+                }
+            }
+        }
+    }
+
+    /// add installed registry crate of provided `name-version` format from all
+    /// registry to plan
+    pub fn add_registry_crate_named(&mut self, full_name: &str, reason: Reason) {
+        let Some(requested) = CrateId::parse(full_name, "") else {
+            return;
+        };
+        let crate_list = self.crate_list;
+        for crate_id in crate_list.installed_registry() {
+            if crate_id.name() == requested.name() && crate_id.version() == requested.version() {
+                self.add_registry_crate(crate_id, reason);
+            }
+        }
+    }
+
+    /// add least recently used registry source, then registry archive and then git
+    /// checkout to plan until size of registry and git directory fits in budget.
+    /// Crate which is not followed by crate filter or used by project is never
+    /// added. Return size of registry and git directory after plan is executed
+    pub fn add_budget(
+        &mut self,
+        crate_filter: &CrateFilter,
+        budget: u64,
+        current_size: u64,
+    ) -> u64 {
+        let crate_list = self.crate_list;
+        let crate_detail = self.crate_detail;
+        let mut registry_crates = crate_list
+            .installed_registry()
+            .iter()
+            .filter(|crate_id| {
+                !crate_list.used_registry().contains(crate_id)
+                    && crate_filter.follow_registry(crate_id)
+            })
+            .collect::<Vec<_>>();
+        // crate without last used time is treated as least recently used
+        registry_crates
+            .sort_by_key(|crate_id| crate_detail.registry_crates_last_used().get(*crate_id));
+        let mut git_crates = crate_list
+            .installed_git()
+            .iter()
+            .filter(|git_id| {
+                git_id.rev().is_some()
+                    && !crate_list.used_git().contains(git_id)
+                    && crate_filter.follow_git(git_id)
+            })
+            .collect::<Vec<_>>();
+        git_crates.sort_by_key(|git_id| crate_detail.git_crates_last_used().get(*git_id));
+
+        for crate_id in &registry_crates {
+            if current_size.saturating_sub(self.total_size()) <= budget {
+                break;
+            }
+            self.add_registry_source(crate_id, Reason::Budget);
+        }
+        for crate_id in &registry_crates {
+            if current_size.saturating_sub(self.total_size()) <= budget {
+                break;
+            }
+            self.add_registry_crate(crate_id, Reason::Budget);
+        }
+        for git_id in &git_crates {
+            if current_size.saturating_sub(self.total_size()) <= budget {
+                break;
+            }
+            self.add_git_crate(git_id, Reason::Budget);
+        }
+        current_size.saturating_sub(self.total_size())
+    }
+
+    /// add path of installed git crate to plan, crate without revision refers to
+    /// git db and other refers to git checkout of revision
+    pub fn add_git_crate(&mut self, git_id: &GitId, reason: Reason) {
+        if !self.crate_list.installed_git().contains(git_id) {
+            return;
+        }
+        let crate_name = git_id.to_string();
+        match git_id.rev() {
+            None => {
+                let size = size_of(self.crate_detail.git_crates_source(), git_id);
+                let path = self.db_dir.join(git_id.db());
+                self.add_entry(path, EntryKind::GitDb, &crate_name, size, reason);
+            }
+            Some(rev) => {
+                let size = size_of(self.crate_detail.git_crates_archive(), git_id);
+                let path = self.checkout_dir.join(git_id.db()).join(rev);
+                self.add_entry(path, EntryKind::GitCheckout, &crate_name, size, reason);
+            }
+        }
+    }
+
+    /// add installed git crate to plan by its `name-rev` or `name-HEAD` format,
+    /// name can also be name of git db directory
+    pub fn add_git_crate_named(&mut self, full_name: &str, reason: Reason) {
+        let crate_list = self.crate_list;
+        for git_id in crate_list.installed_git() {
+            let rev = git_id.rev().unwrap_or("HEAD");
+            if git_id.to_string() == full_name || format!("{}-{rev}", git_id.db()) == full_name {
+                self.add_git_crate(git_id, reason);
+            }
+        }
+    }
+
+    /// add old registry and/or git crates to plan, crate which is excluded by
+    /// crate filter is not added
+    pub fn add_old(&mut self, crate_filter: &CrateFilter, registry: bool, git: bool) {
+        let crate_list = self.crate_list;
+        if registry {
+            for crate_id in crate_list.old_registry() {
+                if crate_filter.follow_registry(crate_id) {
+                    self.add_registry_crate(crate_id, Reason::Old);
+                }
+            }
+        }
+        if git {
+            for git_id in crate_list.old_git() {
+                if crate_filter.follow_git(git_id) {
+                    self.add_git_crate(git_id, Reason::Old);
+                }
+            }
+        }
+    }
+
+    /// add orphan registry and/or git crates to plan, crate which is excluded
+    /// by crate filter is not added
+    pub fn add_orphan(&mut self, crate_filter: &CrateFilter, registry: bool, git: bool) {
+        let crate_list = self.crate_list;
+        if registry {
+            for crate_id in crate_list.orphan_registry() {
+                if crate_filter.follow_registry(crate_id) {
+                    self.add_registry_crate(crate_id, Reason::Orphan);
+                }
+            }
+        }
+        if git {
+            for git_id in crate_list.orphan_git() {
+                if crate_filter.follow_git(git_id) {
+                    self.add_git_crate(git_id, Reason::Orphan);
+                }
+            }
+        }
+    }
+
+    /// add registry and/or git crates which are not used for more than max age to
+    /// plan, crate which is excluded by crate filter or used by project is not
+    /// added
+    pub fn add_stale(
+        &mut self,
+        crate_filter: &CrateFilter,
+        max_age: Duration,
+        registry: bool,
+        git: bool,
+    ) {
+        let crate_list = self.crate_list;
+        if registry {
+            for crate_id in crate_list.stale_registry(self.crate_detail, max_age) {
+                if !crate_list.used_registry().contains(&crate_id)
+                    && crate_filter.follow_registry(&crate_id)
+                {
+                    self.add_registry_crate(&crate_id, Reason::Stale);
+                }
+            }
+        }
+        if git {
+            for git_id in crate_list.stale_git(self.crate_detail, max_age) {
+                if !crate_list.used_git().contains(&git_id) && crate_filter.follow_git(&git_id) {
+                    self.add_git_crate(&git_id, Reason::Stale);
+                }
+            }
+        }
+    }
+
+    /// add installed registry and/or git crates to plan by following include
+    /// and exclude rules of crate filter
+    pub fn add_all(&mut self, crate_filter: &CrateFilter, registry: bool, git: bool) {
+        let crate_list = self.crate_list;
+        if registry {
+            for crate_id in crate_list.installed_registry() {
+                if crate_filter.follow_registry(crate_id) {
+                    self.add_registry_crate(crate_id, Reason::All);
+                }
+            }
+            // index cache entry of crate which is not installed is fetched while
+            // resolving dependency, these are mostly present in sparse index
+            let mut index_cache = Vec::new();
+            for registry_index in &self.registry_index {
+                let registry = registry_index.name();
+                for (crate_name, path, size) in registry_index.cache_entries() {
+                    let installed = crate_list.installed_registry().iter().any(|crate_id| {
+                        crate_id.registry() == registry
+                            && crate_id.name().to_lowercase() == crate_name
+                    });
+                    if !installed && crate_filter.follow(&crate_name, &crate_name, None) {
+                        index_cache.push((crate_name, path, size));
+                    }
+                }
+            }
+            for (crate_name, path, size) in index_cache {
+                self.add_entry(path, EntryKind::IndexCache, &crate_name, size, Reason::All);
+            }
+        }
+        if git {
+            for git_id in crate_list.installed_git() {
+                if crate_filter.follow_git(git_id) {
+                    self.add_git_crate(git_id, Reason::All);
+                }
+            }
+        }
+    }
+
+    /// return all entries of plan sorted by path
+    pub fn entries(&self) -> impl Iterator<Item = &PlanEntry> {
+        self.entries.values()
+    }
+
+    /// return true if plan doesn't contain any path
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// return total size of all path present in plan in bytes
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(PlanEntry::size).sum()
+    }
+
+    /// Execute plan by removing all path, on dry run nothing is removed and
+    /// when trash run is provided path is moved to trash instead. Return list of
+    /// entries which are removed or would be removed on dry run, path which
+    /// failed to be removed is not returned and its error is pushed to errors
+    pub fn execute(
+        &self,
+        dry_run: bool,
+        mut trash_run: Option<&mut TrashRun>,
+        errors: &mut Vec<TrimError>,
+    ) -> Vec<&PlanEntry> {
+        if dry_run {
+            return self.entries.values().collect();
+        }
+        let mut removed = Vec::new();
+        for entry in self.entries.values() {
+            let result = match trash_run.as_deref_mut() {
+                Some(trash_run) => trash_run.move_path(&entry.path, entry.size),
+                None => remove_path(&entry.path),
+            };
+            match result {
+                Ok(()) => removed.push(entry),
+                Err(e) => errors.push(e),
+            }
+        }
+        for registry_index in &self.registry_index {
+            let index_cache_dir = registry_index.cache_dir();
+            let removed_cache = removed.iter().any(|entry| {
+                entry.kind == EntryKind::IndexCache && entry.path.starts_with(&index_cache_dir)
+            });
+            if removed_cache {
+                if let Err(e) = remove_empty_index_cache_dir(&index_cache_dir) {
+                    errors.push(e);
+                }
+            }
+        }
+        removed
+    }
+
+    // add extracted source of registry crate to plan
+    fn add_registry_source(&mut self, crate_id: &CrateId, reason: Reason) {
+        let crate_name = crate_id.to_string();
+        let source_size = size_of(self.crate_detail.registry_crates_source(), crate_id);
+        let path = self
+            .registry_src_dir
+            .join(crate_id.registry())
+            .join(&crate_name);
+        if path.is_dir() {
+            self.add_entry(
+                path,
+                EntryKind::RegistrySource,
+                &crate_name,
+                source_size,
+                reason,
+            );
+        }
+    }
+
+    // add path to plan or add reason to already present path
+    fn add_entry(
+        &mut self,
+        path: PathBuf,
+        kind: EntryKind,
+        crate_name: &str,
+        size: u64,
+        reason: Reason,
+    ) {
+        self.entries
+            .entry(path.clone())
+            .or_insert_with(|| PlanEntry {
+                path,
+                kind,
+                crate_name: crate_name.to_string(),
+                size,
+                reasons: BTreeSet::new(),
+            })
+            .reasons
+            .insert(reason);
+    }
+}
+
+// get size of crate from size information
+fn size_of<K, Q>(size_detail: &HashMap<K, u64>, crate_name: &Q) -> u64
+where
+    K: Borrow<Q> + Eq + Hash,
+    Q: Eq + Hash + ?Sized,
+{
+    size_detail.get(crate_name).copied().unwrap_or(0)
+}
+
+// remove file or directory
+fn remove_path(path: &Path) -> Result<(), TrimError> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).map_err(io_error(path, "remove directory"))
+    } else {
+        fs::remove_file(path).map_err(io_error(path, "remove file"))
+    }
+}
+
+// remove empty directory present inside index cache directory
+fn remove_empty_index_cache_dir(path: &Path) -> Result<(), TrimError> {
+    let entries = read_dir(path)?;
+    if entries.is_empty() {
+        fs::remove_dir(path).map_err(io_error(path, "remove empty index cache directory"))?;
+    } else {
+        for path in entries {
+            if path.is_dir() {
+                remove_empty_index_cache_dir(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
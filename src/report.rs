@@ -0,0 +1,435 @@
+use crate::{
+    crate_id::CrateId, git_id::GitId, CargoConfig, CrateDetail, CrateList, HistoryEntry,
+    HistorySummary, PlanEntry, ProjectUsage, TrimError,
+};
+use serde_derive::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// version of json report, version is increased whenever existing field of
+/// report is changed or removed
+pub const REPORT_VERSION: u32 = 3;
+
+/// Machine readable report of single invocation which can be serialized to
+/// json
+#[derive(Serialize)]
+pub struct Report {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crates: Option<Vec<CrateReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<QueryReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    top: Vec<TopReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    projects: Vec<ProjectReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    removal: Option<RemovalReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget: Option<BudgetReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    history: Option<HistoryReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// Information of single crate present in report
+#[derive(Serialize)]
+pub struct CrateReport {
+    name: String,
+    version: String,
+    source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_db: Option<String>,
+    archive_size: u64,
+    source_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_used: Option<u64>,
+    category: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    workspace: Vec<PathBuf>,
+}
+
+/// Size of different cargo home directory in bytes
+#[derive(Default, Serialize)]
+pub struct QueryReport {
+    /// size of bin directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bin: Option<u64>,
+    /// size of git directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<u64>,
+    /// size of git checkout directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_checkout: Option<u64>,
+    /// size of git db directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_db: Option<u64>,
+    /// size of registry directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<u64>,
+    /// size of registry cache directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_cache: Option<u64>,
+    /// size of registry index directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_index: Option<u64>,
+    /// size of registry src directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_src: Option<u64>,
+    /// size of each registry present in registry directory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<RegistryQuery>,
+    /// size of path quarantined in trash directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash: Option<u64>,
+    /// total size of all queried directory
+    pub total: u64,
+}
+
+/// Size of single registry present in cargo home in bytes
+#[derive(Serialize)]
+pub struct RegistryQuery {
+    /// name of registry directory
+    pub name: String,
+    /// index url of registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// size of registry cache directory
+    pub cache: u64,
+    /// kind of registry index either git or sparse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_kind: Option<String>,
+    /// number of crates present in registry index cache
+    pub index_crates: usize,
+    /// size of registry index directory
+    pub index: u64,
+    /// size of registry src directory
+    pub src: u64,
+}
+
+/// Top crates of certain type sorted by size
+#[derive(Serialize)]
+pub struct TopReport {
+    kind: String,
+    crates: Vec<TopCrate>,
+}
+
+#[derive(Serialize)]
+struct TopCrate {
+    name: String,
+    size: u64,
+}
+
+/// Size of crates used by project directory
+#[derive(Serialize)]
+pub struct ProjectReport {
+    directory: PathBuf,
+    registry_size: u64,
+    registry_exclusive_size: u64,
+    git_size: u64,
+    git_exclusive_size: u64,
+}
+
+/// Path which are removed or would be removed on dry run
+#[derive(Serialize)]
+pub struct RemovalReport {
+    dry_run: bool,
+    size: u64,
+    freed: u64,
+    quarantined: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trash_id: Option<String>,
+    removed: Vec<RemovedPath>,
+}
+
+#[derive(Serialize)]
+struct RemovedPath {
+    path: PathBuf,
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crate_name: Option<String>,
+    size: u64,
+    reasons: Vec<String>,
+}
+
+impl RemovalReport {
+    // add removed path and count its size as freed or quarantined
+    fn add(&mut self, removed: RemovedPath) {
+        self.size += removed.size;
+        if self.trash_id.is_some() {
+            self.quarantined += removed.size;
+        } else {
+            self.freed += removed.size;
+        }
+        self.removed.push(removed);
+    }
+}
+
+/// Size of registry and git directory compared with budget
+#[derive(Serialize)]
+pub struct BudgetReport {
+    budget: u64,
+    size: u64,
+    over_budget: u64,
+    under_budget: u64,
+}
+
+/// Previous run recorded in history file along with their summary
+#[derive(Serialize)]
+pub struct HistoryReport {
+    runs: Vec<HistoryEntry>,
+    summary: HistorySummary,
+}
+
+impl Report {
+    /// create new empty `Report`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            crates: None,
+            query: None,
+            top: Vec::new(),
+            projects: Vec::new(),
+            removal: None,
+            budget: None,
+            history: None,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// add registry crates to report, crate which is already present in report is
+    /// skipped
+    pub fn add_registry_crates(
+        &mut self,
+        cargo_config: &CargoConfig,
+        crate_list: &CrateList,
+        crate_detail: &CrateDetail,
+        list: &[CrateId],
+    ) {
+        let crates = self.crates.get_or_insert_with(Vec::new);
+        for crate_id in list {
+            let version = crate_id.version().to_string();
+            if crates.iter().any(|present| {
+                present.source == "registry"
+                    && present.name == crate_id.name()
+                    && present.version == version
+                    && present.registry.as_deref() == Some(crate_id.registry())
+            }) {
+                continue;
+            }
+            let mut category = Vec::new();
+            if crate_list.old_registry().contains(crate_id) {
+                category.push("old");
+            }
+            if crate_list.orphan_registry().contains(crate_id) {
+                category.push("orphan");
+            }
+            if crate_list.used_registry().contains(crate_id) {
+                category.push("used");
+            }
+            crates.push(CrateReport {
+                name: crate_id.name().to_string(),
+                version,
+                source: "registry",
+                registry: Some(crate_id.registry().to_string()),
+                registry_url: cargo_config
+                    .url(crate_id.registry())
+                    .map(ToString::to_string),
+                git_db: None,
+                archive_size: size_of(crate_detail.registry_crates_archive().get(crate_id)),
+                source_size: size_of(crate_detail.registry_crates_source().get(crate_id)),
+                last_used: unix_time(crate_detail.registry_crates_last_used().get(crate_id)),
+                category,
+                workspace: crate_list.registry_workspaces(crate_id).to_vec(),
+            });
+        }
+    }
+
+    /// add git crates to report, crate which is already present in report is
+    /// skipped
+    pub fn add_git_crates(
+        &mut self,
+        crate_list: &CrateList,
+        crate_detail: &CrateDetail,
+        list: &[GitId],
+    ) {
+        let crates = self.crates.get_or_insert_with(Vec::new);
+        for git_id in list {
+            let rev = git_id.rev().unwrap_or("HEAD");
+            if crates.iter().any(|present| {
+                present.source == "git"
+                    && present.git_db.as_deref() == Some(git_id.db())
+                    && present.version == rev
+            }) {
+                continue;
+            }
+            let mut category = Vec::new();
+            if crate_list.old_git().contains(git_id) {
+                category.push("old");
+            }
+            if crate_list.orphan_git().contains(git_id) {
+                category.push("orphan");
+            }
+            if crate_list.used_git().contains(git_id) {
+                category.push("used");
+            }
+            crates.push(CrateReport {
+                name: git_id.name().to_string(),
+                version: rev.to_string(),
+                source: "git",
+                registry: None,
+                registry_url: None,
+                git_db: Some(git_id.db().to_string()),
+                archive_size: size_of(crate_detail.git_crates_archive().get(git_id)),
+                source_size: size_of(crate_detail.git_crates_source().get(git_id)),
+                last_used: unix_time(crate_detail.git_crates_last_used().get(git_id)),
+                category,
+                workspace: crate_list.git_workspaces(git_id).to_vec(),
+            });
+        }
+    }
+
+    /// set size information of queried directory
+    pub fn set_query(&mut self, query: QueryReport) {
+        self.query = Some(query);
+    }
+
+    /// add top crates of certain type, sizes list should be sorted by size
+    pub fn add_top(&mut self, kind: &str, sizes: &[(String, u64)]) {
+        self.top.push(TopReport {
+            kind: kind.to_string(),
+            crates: sizes
+                .iter()
+                .map(|(name, size)| TopCrate {
+                    name: name.clone(),
+                    size: *size,
+                })
+                .collect(),
+        });
+    }
+
+    /// add size of crates used by each project directory
+    pub fn add_projects(&mut self, usage: &[ProjectUsage]) {
+        self.projects
+            .extend(usage.iter().map(|project| ProjectReport {
+                directory: project.directory().clone(),
+                registry_size: project.registry_size(),
+                registry_exclusive_size: project.registry_exclusive_size(),
+                git_size: project.git_size(),
+                git_exclusive_size: project.git_exclusive_size(),
+            }));
+    }
+
+    /// add path removed by removal plan, path are quarantined instead of freed
+    /// when they are moved to trash run
+    pub fn add_removal(&mut self, dry_run: bool, trash_id: Option<&str>, removed: &[&PlanEntry]) {
+        let removal = self.removal(dry_run, trash_id);
+        for entry in removed {
+            removal.add(RemovedPath {
+                path: entry.path().clone(),
+                kind: entry.kind().to_string(),
+                crate_name: Some(entry.crate_name().to_string()),
+                size: entry.size(),
+                reasons: entry.reasons().iter().map(ToString::to_string).collect(),
+            });
+        }
+    }
+
+    /// add folder removed as whole such as by light cleanup or wipe, folder is
+    /// quarantined instead of freed when it is moved to trash run
+    pub fn add_removed_folder(
+        &mut self,
+        (dry_run, trash_id): (bool, Option<&str>),
+        folder: &Path,
+        kind: &str,
+        size: u64,
+    ) {
+        self.removal(dry_run, trash_id).add(RemovedPath {
+            path: folder.to_path_buf(),
+            kind: kind.to_string(),
+            crate_name: None,
+            size,
+            reasons: Vec::new(),
+        });
+    }
+
+    // get removal report, report is created when no path is removed yet
+    fn removal(&mut self, dry_run: bool, trash_id: Option<&str>) -> &mut RemovalReport {
+        self.removal.get_or_insert_with(|| RemovalReport {
+            dry_run,
+            size: 0,
+            freed: 0,
+            quarantined: 0,
+            trash_id: trash_id.map(ToString::to_string),
+            removed: Vec::new(),
+        })
+    }
+
+    /// set budget along with size of registry and git directory after removal
+    pub fn set_budget(&mut self, budget: u64, size: u64) {
+        self.budget = Some(BudgetReport {
+            budget,
+            size,
+            over_budget: size.saturating_sub(budget),
+            under_budget: budget.saturating_sub(size),
+        });
+    }
+
+    /// set previous run of history file along with their summary
+    pub fn set_history(&mut self, entries: &[HistoryEntry]) {
+        self.history = Some(HistoryReport {
+            runs: entries.to_vec(),
+            summary: HistorySummary::new(entries),
+        });
+    }
+
+    /// add warning which occurred during invocation, warning doesn't change
+    /// exit code
+    pub fn add_warning(&mut self, warning: &TrimError) {
+        self.warnings.push(warning.to_string());
+    }
+
+    /// add error which occurred during invocation
+    pub fn add_error(&mut self, error: &TrimError) {
+        self.errors.push(error.to_string());
+    }
+
+    /// serialize report to pretty json
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if report cannot be serialized
+    pub fn to_json(&self) -> Result<String, TrimError> {
+        serde_json::to_string_pretty(self).map_err(|source| TrimError::Json {
+            path: PathBuf::from("-"),
+            source,
+        })
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// get size from optional size value
+fn size_of(size: Option<&u64>) -> u64 {
+    size.copied().unwrap_or(0)
+}
+
+// convert time to seconds since unix epoch
+fn unix_time(time: Option<&SystemTime>) -> Option<u64> {
+    time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
@@ -0,0 +1,270 @@
+use crate::{
+    error::{io_error, TrimError},
+    list_crate::CargoTomlLocation,
+    project_config::ProjectConfig,
+};
+use ignore::{WalkBuilder, WalkState};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+// directory which only contains build output so it is never scanned
+const TARGET_DIR: &str = "target";
+
+// file whose modification changes result of scan without changing modified time
+// of its directory
+const TRACKED_FILES: [&str; 3] = [".gitignore", ".ignore", ".cargo-trim.toml"];
+
+#[derive(Default, Serialize, Deserialize)]
+struct ScanCache {
+    entries: Vec<CacheEntry>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheEntry {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    // modified time in nanoseconds of all directory and tracked file visited
+    modified: BTreeMap<PathBuf, u64>,
+    manifest: Vec<PathBuf>,
+    project_config: Vec<PathBuf>,
+}
+
+impl CacheEntry {
+    // check if any visited directory or tracked file is modified, added or
+    // removed since scan
+    fn is_fresh(&self) -> bool {
+        self.modified
+            .iter()
+            .all(|(path, modified)| modified_time(path) == Some(*modified))
+    }
+}
+
+/// Scanner which finds directory containing Cargo.toml inside project
+/// directory. Scanner honours `.gitignore` and `.ignore` file, skips `target`
+/// and hidden directory, follows symlink while skipping symlink cycle and walks
+/// directory in parallel. Result is cached along with modified time of visited
+/// directory so project directory which is not modified is not walked again
+pub struct Scanner {
+    max_depth: Option<usize>,
+    cache_file: Option<PathBuf>,
+}
+
+impl Scanner {
+    /// create new scanner without depth limit and cache
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_depth: None,
+            cache_file: None,
+        }
+    }
+
+    /// limit depth of directory scanned inside project directory, project
+    /// directory itself has depth 0
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// set file where result of scan is cached
+    pub fn set_cache_file(&mut self, cache_file: &Path) {
+        self.cache_file = Some(cache_file.to_path_buf());
+    }
+
+    /// scan all directories and list directory containing Cargo.toml along with
+    /// project config file, entry which cannot be read is skipped and its error
+    /// is pushed to errors
+    pub fn scan(&self, directories: &[String], errors: &mut Vec<TrimError>) -> CargoTomlLocation {
+        // cache which cannot be read is treated as empty cache
+        let mut cache = self
+            .cache_file
+            .as_ref()
+            .and_then(|cache_file| fs::read_to_string(cache_file).ok())
+            .and_then(|buffer| serde_json::from_str::<ScanCache>(&buffer).ok())
+            .unwrap_or_default();
+        let mut cache_modified = false;
+        let mut list = CargoTomlLocation::new();
+        for directory in directories {
+            let root = Path::new(directory);
+            if !root.exists() {
+                continue;
+            }
+            let cached = cache.entries.iter().find(|entry| {
+                entry.root == root && entry.max_depth == self.max_depth && entry.is_fresh()
+            });
+            if let Some(entry) = cached {
+                for path in &entry.project_config {
+                    match ProjectConfig::read(path) {
+                        Ok(project_config) => list.add_project_config(project_config),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                for path in &entry.manifest {
+                    list.add_path(path.clone());
+                }
+                continue;
+            }
+            let (entry, project_configs, walk_errors) = walk(root, self.max_depth);
+            for project_config in project_configs {
+                list.add_project_config(Some(project_config));
+            }
+            for path in &entry.manifest {
+                list.add_path(path.clone());
+            }
+            cache
+                .entries
+                .retain(|cached| cached.root != root || cached.max_depth != self.max_depth);
+            // scan which failed is not cached so it is walked again on next run
+            if walk_errors.is_empty() {
+                cache.entries.push(entry);
+                cache_modified = true;
+            } else {
+                errors.extend(walk_errors);
+            }
+        }
+        if let (Some(cache_file), true) = (&self.cache_file, cache_modified) {
+            if let Err(e) = save_cache(cache_file, &cache) {
+                errors.push(e);
+            }
+        }
+        list
+    }
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// walk directory in parallel and return visited directory along with project
+// config file and error occurred while walking
+fn walk(root: &Path, max_depth: Option<usize>) -> (CacheEntry, Vec<ProjectConfig>, Vec<TrimError>) {
+    let modified = Mutex::new(BTreeMap::new());
+    let manifest = Mutex::new(Vec::new());
+    let project_configs = Mutex::new(Vec::<ProjectConfig>::new());
+    let errors = Mutex::new(Vec::new());
+    WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .ignore(true)
+        .require_git(false)
+        .follow_links(true)
+        // Cargo.toml is one level deeper than directory containing it
+        .max_depth(max_depth.map(|max_depth| max_depth + 1))
+        .build_parallel()
+        .run(|| {
+            Box::new(|result| {
+                let entry = match result {
+                    Ok(entry) => entry,
+                    // symlink cycle is skipped silently
+                    Err(e) if is_loop(&e) => return WalkState::Continue,
+                    Err(e) => {
+                        errors
+                            .lock()
+                            .unwrap()
+                            .push(io_error(root, "scan directory")(io::Error::other(
+                                e.to_string(),
+                            )));
+                        return WalkState::Continue;
+                    }
+                };
+                let path = entry.path();
+                let file_type = entry.file_type();
+                if file_type.is_some_and(|file_type| file_type.is_file()) {
+                    if entry.file_name() == "Cargo.toml" {
+                        if let Some(parent) = path.parent() {
+                            manifest.lock().unwrap().push(parent.to_path_buf());
+                        }
+                    }
+                    return WalkState::Continue;
+                }
+                if !file_type.is_some_and(|file_type| file_type.is_dir()) {
+                    return WalkState::Continue;
+                }
+                // project config file of parent directory is always read before
+                // its children are visited
+                if entry.depth() > 0
+                    && (entry.file_name() == TARGET_DIR
+                        || project_configs
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .any(|project_config| project_config.is_skipped(path)))
+                {
+                    return WalkState::Skip;
+                }
+                {
+                    let mut modified = modified.lock().unwrap();
+                    for tracked in std::iter::once(path.to_path_buf())
+                        .chain(TRACKED_FILES.iter().map(|name| path.join(name)))
+                    {
+                        if let Some(time) = modified_time(&tracked) {
+                            modified.insert(tracked, time);
+                        }
+                    }
+                }
+                match ProjectConfig::read(path) {
+                    Ok(Some(project_config)) => {
+                        project_configs.lock().unwrap().push(project_config);
+                    }
+                    Ok(None) => (),
+                    Err(e) => errors.lock().unwrap().push(e),
+                }
+                WalkState::Continue
+            })
+        });
+    let mut manifest = manifest.into_inner().unwrap();
+    manifest.sort();
+    manifest.dedup();
+    let mut project_configs = project_configs.into_inner().unwrap();
+    project_configs.sort_by(|a, b| a.path().cmp(b.path()));
+    let entry = CacheEntry {
+        root: root.to_path_buf(),
+        max_depth,
+        modified: modified.into_inner().unwrap(),
+        manifest,
+        project_config: project_configs
+            .iter()
+            .filter_map(|project_config| project_config.path().parent())
+            .map(Path::to_path_buf)
+            .collect(),
+    };
+    (entry, project_configs, errors.into_inner().unwrap())
+}
+
+// check if error of walk is caused by symlink cycle
+fn is_loop(error: &ignore::Error) -> bool {
+    match error {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_loop(err),
+        ignore::Error::Partial(errors) => errors.iter().all(is_loop),
+        _ => false,
+    }
+}
+
+// get modified time of path in nanoseconds
+fn modified_time(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+    modified
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+}
+
+// write scan cache to cache file
+fn save_cache(cache_file: &Path, cache: &ScanCache) -> Result<(), TrimError> {
+    let buffer = serde_json::to_string(cache).map_err(|source| TrimError::Json {
+        path: cache_file.to_path_buf(),
+        source,
+    })?;
+    fs::write(cache_file, buffer).map_err(io_error(cache_file, "write scan cache"))
+}
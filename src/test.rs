@@ -1,4 +1,94 @@
-use std::{io::Read, process::Command};
+#![allow(clippy::single_char_pattern, clippy::collapsible_str_replace)]
+
+use crate::{
+    cargo_config::{registry_dir_names, CargoConfig},
+    config_file::ConfigFile,
+    crate_detail::CrateDetail,
+    crate_filter::{join_version_req, CrateFilter, CrateRule, FilterMode, RuleKind, RuleSource},
+    crate_id::CrateId,
+    dir_path::DirPath,
+    error::TrimError,
+    git::{git_compress, latest_rev},
+    git_id::GitId,
+    history::{History, HistoryEntry, HistorySummary},
+    list_crate::{CrateList, KeepPolicy},
+    lock_file::LockFile,
+    package_cache_lock::PackageCacheLock,
+    registry_index::{IndexKind, RegistryIndex},
+    removal_plan::{EntryKind, Reason, RemovalPlan},
+    report::{Report, REPORT_VERSION},
+    scanner::Scanner,
+    trash::{Trash, TrashRun},
+    utils::{parse_duration, parse_size},
+};
+use std::{
+    fs::{File, FileTimes},
+    io::Read,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+// temporary directory of test which is removed when dropped so it is cleaned
+// even if test panics
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    // create empty temporary directory, process id is part of name so parallel
+    // run of test binary doesn't share directory
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("cargo-trim-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    // use directory as cargo home and list crates present in it, config file of
+    // cargo trim is stored inside directory
+    fn create_list(
+        &self,
+        config_file: &ConfigFile,
+    ) -> (DirPath, CrateList, CrateDetail, Vec<TrimError>) {
+        let dir_path = DirPath::set_dir_path(
+            self.path.to_str(),
+            self.path.join("cargo_trim_config.toml").to_str(),
+        )
+        .unwrap();
+        let mut crate_detail = CrateDetail::new();
+        let mut errors = Vec::new();
+        let crate_list = CrateList::create_list(
+            &dir_path,
+            config_file,
+            &Scanner::new(),
+            &mut crate_detail,
+            &mut errors,
+        );
+        (dir_path, crate_list, crate_detail, errors)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
 
 // test check trim subcommand help
 #[test]
@@ -145,3 +235,1731 @@ fn test_completions_help() {
         buffer.replace("\r", "").replace("\n", "")
     );
 }
+
+// test check history subcommand help
+#[test]
+fn test_history_help() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("trim")
+        .arg("help")
+        .arg("history")
+        .output()
+        .expect("failed to execute process");
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut buffer = String::new();
+    let mut file = std::fs::File::open("tests/command_output/history.txt").unwrap();
+    file.read_to_string(&mut buffer).unwrap();
+    assert_eq!(
+        output.replace("\r", "").replace("\n", ""),
+        buffer.replace("\r", "").replace("\n", "")
+    );
+}
+
+// test check trash subcommand help
+#[test]
+fn test_trash_help() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("trim")
+        .arg("help")
+        .arg("trash")
+        .output()
+        .expect("failed to execute process");
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut buffer = String::new();
+    let mut file = std::fs::File::open("tests/command_output/trash.txt").unwrap();
+    file.read_to_string(&mut buffer).unwrap();
+    assert_eq!(
+        output.replace("\r", "").replace("\n", ""),
+        buffer.replace("\r", "").replace("\n", "")
+    );
+}
+
+// test check undo subcommand help
+#[test]
+fn test_undo_help() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("trim")
+        .arg("help")
+        .arg("undo")
+        .output()
+        .expect("failed to execute process");
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut buffer = String::new();
+    let mut file = std::fs::File::open("tests/command_output/undo.txt").unwrap();
+    file.read_to_string(&mut buffer).unwrap();
+    assert_eq!(
+        output.replace("\r", "").replace("\n", ""),
+        buffer.replace("\r", "").replace("\n", "")
+    );
+}
+
+// test check why subcommand help
+#[test]
+fn test_why_help() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("--")
+        .arg("trim")
+        .arg("help")
+        .arg("why")
+        .output()
+        .expect("failed to execute process");
+    let output = String::from_utf8(output.stdout).unwrap();
+    let mut buffer = String::new();
+    let mut file = std::fs::File::open("tests/command_output/why.txt").unwrap();
+    file.read_to_string(&mut buffer).unwrap();
+    assert_eq!(
+        output.replace("\r", "").replace("\n", ""),
+        buffer.replace("\r", "").replace("\n", "")
+    );
+}
+
+// test cargo home and config file passed as argument is used instead of
+// default location
+#[test]
+fn test_cargo_home_override() {
+    let cargo_home = TempDir::new("home");
+    let config_dir = TempDir::new("home-config");
+    let config_path = config_dir.join("cargo-trim-home-config.toml");
+    let dir_path = DirPath::set_dir_path(cargo_home.to_str(), config_path.to_str()).unwrap();
+    assert!(config_path.exists());
+    assert_eq!(dir_path.config_dir(), &config_path);
+    assert_eq!(
+        dir_path.history_file(),
+        &config_dir.join("cargo_trim_history.jsonl")
+    );
+    assert_eq!(dir_path.cargo_home(), &cargo_home.to_path_buf());
+    assert_eq!(dir_path.bin_dir(), &cargo_home.join("bin"));
+    assert_eq!(dir_path.db_dir(), &cargo_home.join("git").join("db"));
+    assert_eq!(dir_path.src_dir(), &cargo_home.join("registry").join("src"));
+}
+
+// test invalid config file returns toml error instead of panicking
+#[test]
+fn test_invalid_config_file() {
+    let directory = TempDir::new("invalid-config");
+    let config_path = directory.join("cargo_trim_config.toml");
+    std::fs::write(&config_path, "directory = [").unwrap();
+    let error = ConfigFile::init(&config_path).err().unwrap();
+    assert_eq!(error.exit_code(), 65);
+    assert!(error.to_string().contains("failed to parse toml"));
+}
+
+// test json config file of older version is migrated to toml config file
+#[test]
+fn test_config_file_migrate() {
+    let directory = TempDir::new("migrate-config");
+    let json_path = directory.join("cargo_trim_config.json");
+    let config_path = directory.join("cargo_trim_config.toml");
+    std::fs::write(
+        &json_path,
+        r#"{"directory":["/project"],"include":["serde"],"exclude":["tokio*"]}"#,
+    )
+    .unwrap();
+    let migrated = ConfigFile::migrate(&json_path, &config_path).unwrap();
+    let buffer = std::fs::read_to_string(&config_path).unwrap();
+    let config_file = ConfigFile::init(&config_path).unwrap();
+    assert!(json_path.exists());
+    assert_eq!(migrated.directory(), &vec!["/project".to_string()]);
+    assert!(buffer.contains(r#"exclude = ["tokio*"]"#));
+    assert_eq!(config_file.include(), &vec!["serde".to_string()]);
+    assert_eq!(config_file.exclude(), &vec!["tokio*".to_string()]);
+    assert_eq!(config_file.mode(), FilterMode::Denylist);
+}
+
+// test policy section of config file
+#[test]
+fn test_config_file_policy() {
+    let directory = TempDir::new("policy-config");
+    let config_path = directory.join("cargo_trim_config.toml");
+    std::fs::write(
+        &config_path,
+        "directory = []\n\n[policy]\nmax-age = \"30d\"\nkeep = 2\nbudget = \"2GiB\"\nformat = \
+         \"json\"\n",
+    )
+    .unwrap();
+    let config_file = ConfigFile::init(&config_path).unwrap();
+    let policy = config_file.policy();
+    assert_eq!(policy.max_age(), Some("30d"));
+    assert_eq!(policy.keep(), Some(2));
+    assert_eq!(policy.budget(), Some("2GiB"));
+    assert_eq!(policy.format(), Some("json"));
+    assert!(ConfigFile::new().policy().max_age().is_none());
+}
+
+// test config file without mode uses denylist mode and mode is saved
+#[test]
+fn test_config_file_mode() {
+    let directory = TempDir::new("config-mode");
+    let config_path = directory.join("cargo_trim_config.toml");
+    std::fs::write(&config_path, "directory = []\ninclude = []\nexclude = []\n").unwrap();
+    let mut config_file = ConfigFile::init(&config_path).unwrap();
+    assert_eq!(config_file.mode(), FilterMode::Denylist);
+    config_file.set_mode(FilterMode::Allowlist);
+    config_file.save(&config_path).unwrap();
+    let buffer = std::fs::read_to_string(&config_path).unwrap();
+    let config_file = ConfigFile::init(&config_path).unwrap();
+    assert!(buffer.contains(r#"mode = "allowlist""#));
+    assert_eq!(config_file.mode(), FilterMode::Allowlist);
+}
+
+// test crate added to removal plan for multiple reason is only planned once
+#[test]
+fn test_removal_plan_dedup() {
+    let cargo_home = TempDir::new("removal-plan");
+    let registry = "github.com-1ecc6299db9ec823";
+    let src_dir = cargo_home.join("registry").join("src").join(registry);
+    let cache_dir = cargo_home.join("registry").join("cache").join(registry);
+    for crate_name in &["serde-1.0.1", "serde-1.0.2"] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+        std::fs::write(src_dir.join(crate_name).join("lib.rs"), "").unwrap();
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join(format!("{crate_name}.crate")), "crate").unwrap();
+    }
+    let config_file = ConfigFile::new();
+    let (dir_path, crate_list, crate_detail, mut errors) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    removal_plan.add_old(&CrateFilter::new(&config_file).unwrap(), true, true);
+    removal_plan.add_all(&CrateFilter::new(&config_file).unwrap(), true, true);
+    removal_plan.add_registry_crate_named("serde-1.0.1", Reason::Requested);
+    let removed = removal_plan.execute(true, None, &mut errors);
+    let removed_size = removed.iter().map(|entry| entry.size()).sum::<u64>();
+    assert!(errors.is_empty());
+    assert_eq!(removed.len(), 4);
+    let old_entries = removal_plan
+        .entries()
+        .filter(|entry| entry.crate_name() == "serde-1.0.1")
+        .collect::<Vec<_>>();
+    assert_eq!(old_entries.len(), 2);
+    for entry in old_entries {
+        assert_eq!(
+            entry.reasons().iter().copied().collect::<Vec<_>>(),
+            vec![Reason::Old, Reason::All, Reason::Requested]
+        );
+    }
+    assert_eq!(removed_size, removal_plan.total_size());
+    assert_eq!(removed_size, 2 * 5);
+}
+
+// test json report contains version and crate information
+#[test]
+fn test_json_report() {
+    let cargo_home = TempDir::new("json-report");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    std::fs::create_dir_all(src_dir.join("serde-1.0.1")).unwrap();
+    std::fs::write(src_dir.join("serde-1.0.1").join("lib.rs"), "lib").unwrap();
+    let (_, crate_list, crate_detail, _) = cargo_home.create_list(&ConfigFile::new());
+    let mut report = Report::new();
+    report.add_registry_crates(
+        &CargoConfig::new(),
+        &crate_list,
+        &crate_detail,
+        crate_list.installed_registry(),
+    );
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["version"], REPORT_VERSION);
+    let crates = json["crates"].as_array().unwrap();
+    assert_eq!(crates.len(), 1);
+    assert_eq!(crates[0]["name"], "serde");
+    assert_eq!(crates[0]["version"], "1.0.1");
+    assert_eq!(crates[0]["source"], "registry");
+    assert_eq!(crates[0]["registry"], "github.com-1ecc6299db9ec823");
+    assert_eq!(
+        crates[0]["registry_url"],
+        "https://github.com/rust-lang/crates.io-index"
+    );
+    assert_eq!(crates[0]["source_size"], 3);
+    assert_eq!(crates[0]["category"][0], "orphan");
+}
+
+// test stdout of light cleanup and wipe in json format is single json document
+// which contains removed folder
+#[test]
+fn test_json_cleanup_output() {
+    let temp_dir = TempDir::new("json-cleanup-output");
+    let cargo_home = temp_dir.join("cargo-home");
+    let src_dir = cargo_home.join("registry").join("src");
+    let index_cache = cargo_home
+        .join("registry")
+        .join("index")
+        .join("github.com-1ecc6299db9ec823")
+        .join(".cache");
+    std::fs::create_dir_all(
+        src_dir
+            .join("github.com-1ecc6299db9ec823")
+            .join("serde-1.0.1"),
+    )
+    .unwrap();
+    std::fs::create_dir_all(&index_cache).unwrap();
+    std::fs::write(index_cache.join("serde"), "serde").unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new("cargo")
+            .args(["run", "--", "trim", "--format", "json", "--cargo-home"])
+            .arg(&cargo_home)
+            .arg("--config")
+            .arg(temp_dir.join("cargo_trim_config.toml"))
+            .args(args)
+            .output()
+            .expect("failed to execute process");
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let json = run(&["--light", "--dry-run"]);
+    let removed = json["removal"]["removed"].as_array().unwrap();
+    assert_eq!(json["removal"]["dry_run"], true);
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed[0]["path"], src_dir.to_str().unwrap());
+    assert_eq!(removed[0]["kind"], "registry source");
+    assert_eq!(removed[1]["path"], index_cache.to_str().unwrap());
+    assert_eq!(removed[1]["size"], 5);
+    assert!(src_dir.exists());
+
+    let json = run(&["--wipe", "src"]);
+    let removed = json["removal"]["removed"].as_array().unwrap();
+    assert_eq!(json["removal"]["dry_run"], false);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0]["path"], src_dir.to_str().unwrap());
+    assert!(!src_dir.exists());
+}
+
+// test parsing of max age duration
+#[test]
+fn test_parse_duration() {
+    assert_eq!(
+        parse_duration("45s", "--max-age").unwrap(),
+        Duration::from_secs(45)
+    );
+    assert_eq!(
+        parse_duration("30d", "--max-age").unwrap(),
+        Duration::from_hours(30 * 24)
+    );
+    assert_eq!(
+        parse_duration("12w", "--max-age").unwrap(),
+        Duration::from_hours(12 * 7 * 24)
+    );
+    assert!(parse_duration("30", "--max-age").is_err());
+    assert!(parse_duration("d", "--max-age").is_err());
+    assert!(parse_duration("3y", "--max-age").is_err());
+    assert!(matches!(
+        parse_duration("3y", "--since"),
+        Err(TrimError::InvalidValue {
+            argument: "--since",
+            ..
+        })
+    ));
+}
+
+// test crate not used for more than max age is listed as stale
+#[test]
+fn test_stale_crates() {
+    let cargo_home = TempDir::new("stale");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    for crate_name in &["serde-1.0.1", "serde-1.0.2"] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+        std::fs::write(src_dir.join(crate_name).join("Cargo.toml"), "").unwrap();
+    }
+    let old_time = SystemTime::now() - Duration::from_hours(60 * 24);
+    File::options()
+        .write(true)
+        .open(src_dir.join("serde-1.0.1").join("Cargo.toml"))
+        .unwrap()
+        .set_times(
+            FileTimes::new()
+                .set_accessed(old_time)
+                .set_modified(old_time),
+        )
+        .unwrap();
+    let (_, crate_list, crate_detail, _) = cargo_home.create_list(&ConfigFile::new());
+    let max_age = parse_duration("30d", "--max-age").unwrap();
+    assert_eq!(
+        crate_list.stale_registry(&crate_detail, max_age),
+        vec![CrateId::parse("serde-1.0.1", "github.com-1ecc6299db9ec823").unwrap()]
+    );
+    assert!(crate_list.stale_git(&crate_detail, max_age).is_empty());
+}
+
+// test stale crate which is excluded by crate filter or used by project is not
+// added to removal plan
+#[test]
+fn test_removal_plan_stale() {
+    let cargo_home = TempDir::new("removal-plan-stale");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    let old_time = SystemTime::now() - Duration::from_hours(60 * 24);
+    for crate_name in &["libc-0.2.1", "rand-0.7.0", "serde-1.0.2"] {
+        let cargo_toml = src_dir.join(crate_name).join("Cargo.toml");
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+        File::create(&cargo_toml)
+            .unwrap()
+            .set_times(
+                FileTimes::new()
+                    .set_accessed(old_time)
+                    .set_modified(old_time),
+            )
+            .unwrap();
+    }
+    let project = cargo_home.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("Cargo.toml"), "").unwrap();
+    std::fs::write(
+        project.join("Cargo.lock"),
+        "[[package]]\nname = \"serde\"\nversion = \"1.0.2\"\nsource = \
+         \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("libc");
+    config_file.add_directory(project.to_str().unwrap());
+    let (dir_path, crate_list, crate_detail, errors) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    let max_age = parse_duration("30d", "--max-age").unwrap();
+    removal_plan.add_stale(
+        &CrateFilter::new(&config_file).unwrap(),
+        max_age,
+        true,
+        true,
+    );
+    assert!(errors.is_empty());
+    assert_eq!(crate_list.stale_registry(&crate_detail, max_age).len(), 3);
+    let planned = removal_plan
+        .entries()
+        .map(|entry| entry.crate_name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(planned, vec!["rand-0.7.0".to_string()]);
+}
+
+// test parsing of budget size
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("512").unwrap(), 512);
+    assert_eq!(parse_size("500MB").unwrap(), 500_000_000);
+    assert_eq!(parse_size("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+    assert_eq!(parse_size("1.5kib").unwrap(), 1536);
+    assert!(parse_size("2XB").is_err());
+    assert!(parse_size("GB").is_err());
+}
+
+// test budget evict least recently used source first and keep excluded crate
+#[test]
+fn test_removal_plan_budget() {
+    let cargo_home = TempDir::new("budget");
+    let registry = "github.com-1ecc6299db9ec823";
+    let src_dir = cargo_home.join("registry").join("src").join(registry);
+    let cache_dir = cargo_home.join("registry").join("cache").join(registry);
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    for (days, crate_name) in [(30, "libc-0.2.1"), (20, "rand-0.7.0"), (10, "serde-1.0.1")] {
+        let cargo_toml = src_dir.join(crate_name).join("Cargo.toml");
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+        std::fs::write(&cargo_toml, "0123456789").unwrap();
+        std::fs::write(cache_dir.join(format!("{crate_name}.crate")), "01234").unwrap();
+        let time = SystemTime::now() - Duration::from_hours(days * 24);
+        File::options()
+            .write(true)
+            .open(&cargo_toml)
+            .unwrap()
+            .set_times(FileTimes::new().set_accessed(time).set_modified(time))
+            .unwrap();
+    }
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("libc");
+    let (dir_path, crate_list, crate_detail, _) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    let size = removal_plan.add_budget(&CrateFilter::new(&config_file).unwrap(), 40, 45);
+    assert_eq!(size, 35);
+    let planned = removal_plan
+        .entries()
+        .map(|entry| (entry.crate_name(), entry.kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(planned, vec![("rand-0.7.0", EntryKind::RegistrySource)]);
+}
+
+// test parsing of crate name and version from registry directory name
+#[test]
+fn test_crate_id_parse() {
+    for (full_name, name, version) in &[
+        ("serde-1.0.1", "serde", "1.0.1"),
+        ("md-5-0.10.0", "md-5", "0.10.0"),
+        ("x25519-dalek-1.1.0", "x25519-dalek", "1.1.0"),
+        ("foo-1.0.0-alpha.1", "foo", "1.0.0-alpha.1"),
+        ("foo-bar-2.0.0-rc-1", "foo-bar", "2.0.0-rc-1"),
+        ("foo-1.0.0+build.5", "foo", "1.0.0+build.5"),
+    ] {
+        let crate_id = CrateId::parse(full_name, "").unwrap();
+        assert_eq!(crate_id.name(), *name);
+        assert_eq!(crate_id.version().to_string(), *version);
+        assert_eq!(crate_id.to_string(), *full_name);
+    }
+    for full_name in &[
+        "serde",
+        "serde-1.0",
+        "-1.0.0",
+        "serde-latest",
+        "serde-01.0.0",
+    ] {
+        assert!(CrateId::parse(full_name, "").is_none());
+    }
+}
+
+// test only crate which is not highest semver version of its name is old
+#[test]
+fn test_old_semver() {
+    let cargo_home = TempDir::new("old-semver");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    for crate_name in &[
+        "foo-1.0.0-alpha.1",
+        "foo-1.0.0",
+        "foo-1.10.0",
+        "foo-1.9.0",
+        "md-5-0.9.1",
+        "md-5-0.10.0",
+        "md-0.1.0",
+        "bar-2.0.0-beta.2",
+        "bar-2.0.0-beta.10",
+    ] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+    }
+    let (_, crate_list, _, _) = cargo_home.create_list(&ConfigFile::new());
+    let mut old = crate_list
+        .old_registry()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    old.sort();
+    assert_eq!(
+        old,
+        vec![
+            "bar-2.0.0-beta.2",
+            "foo-1.0.0",
+            "foo-1.0.0-alpha.1",
+            "foo-1.9.0",
+            "md-5-0.9.1"
+        ]
+    );
+}
+
+// test keep policy keeps newest versions per crate or per compatible range
+#[test]
+fn test_keep_policy() {
+    let cargo_home = TempDir::new("keep-policy");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    for crate_name in &[
+        "foo-0.3.1",
+        "foo-0.3.2",
+        "foo-1.0.0",
+        "foo-1.2.0",
+        "bar-0.1.0",
+    ] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+    }
+    let checkout_dir = cargo_home
+        .join("git")
+        .join("checkouts")
+        .join("baz-0123456789abcdef");
+    let old_time = SystemTime::now() - Duration::from_hours(24);
+    for (rev, time) in &[("1111111", old_time), ("2222222", SystemTime::now())] {
+        std::fs::create_dir_all(checkout_dir.join(rev)).unwrap();
+        File::create(checkout_dir.join(rev).join("Cargo.toml"))
+            .unwrap()
+            .set_times(FileTimes::new().set_accessed(*time).set_modified(*time))
+            .unwrap();
+    }
+    let (_, mut crate_list, crate_detail, _) = cargo_home.create_list(&ConfigFile::new());
+    let old_registry = |crate_list: &CrateList| {
+        crate_list
+            .old_registry()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+    let old_git = |crate_list: &CrateList| {
+        crate_list
+            .old_git()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        old_registry(&crate_list),
+        vec!["foo-0.3.1", "foo-0.3.2", "foo-1.0.0"]
+    );
+    assert_eq!(old_git(&crate_list), vec!["baz-1111111", "baz-2222222"]);
+
+    crate_list.set_keep_policy(KeepPolicy::new(2, false), &crate_detail);
+    assert_eq!(old_registry(&crate_list), vec!["foo-0.3.1", "foo-0.3.2"]);
+    assert_eq!(old_git(&crate_list), vec!["baz-1111111"]);
+
+    crate_list.set_keep_policy(KeepPolicy::new(1, true), &crate_detail);
+    assert_eq!(old_registry(&crate_list), vec!["foo-0.3.1", "foo-1.0.0"]);
+    assert_eq!(old_git(&crate_list), vec!["baz-1111111", "baz-2222222"]);
+}
+
+// test same crate from different registry is treated as different crate
+#[test]
+fn test_multiple_registry() {
+    let cargo_home = TempDir::new("multiple-registry");
+    let registries = [
+        "github.com-1ecc6299db9ec823",
+        "my-registry.example.com-0123456789abcdef",
+    ];
+    for registry in &registries {
+        let src_dir = cargo_home.join("registry").join("src").join(registry);
+        let cache_dir = cargo_home.join("registry").join("cache").join(registry);
+        std::fs::create_dir_all(src_dir.join("foo-1.0.0")).unwrap();
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join("foo-1.0.0.crate"), "crate").unwrap();
+    }
+    let mut cargo_config = CargoConfig::new();
+    cargo_config.add_registry("internal", "https://my-registry.example.com/git/index");
+    let (dir_path, mut crate_list, crate_detail, _) = cargo_home.create_list(&ConfigFile::new());
+    assert_eq!(crate_list.installed_registry().len(), 2);
+    assert!(crate_list.old_registry().is_empty());
+
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    let internal = CrateId::parse("foo-1.0.0", registries[1]).unwrap();
+    removal_plan.add_registry_crate(&internal, Reason::Requested);
+    assert!(removal_plan
+        .entries()
+        .all(|entry| entry.path().to_string_lossy().contains(registries[1])));
+    assert_eq!(removal_plan.entries().count(), 2);
+
+    crate_list.filter_registry(|registry| cargo_config.matches(registry, "internal"));
+    assert_eq!(crate_list.installed_registry(), &vec![internal]);
+    assert_eq!(
+        cargo_config.url(registries[1]),
+        Some("https://my-registry.example.com/git/index")
+    );
+    assert!(cargo_config.matches(registries[0], "crates-io"));
+    assert!(cargo_config.matches(
+        registries[0],
+        "https://github.com/rust-lang/crates.io-index"
+    ));
+    assert!(cargo_config.matches("index.crates.io-6f17d22bba15001f", "crates-io"));
+    assert!(!cargo_config.matches(registries[1], "crates-io"));
+}
+
+// test registry directory name is computed from full index url and crate used
+// from one registry doesn't keep crate of other registry with same host
+#[test]
+fn test_registry_identity() {
+    assert_eq!(
+        registry_dir_names("registry+https://github.com/rust-lang/crates.io-index"),
+        vec![
+            "github.com-1ecc6299db9ec823".to_string(),
+            "github.com-25cdd57fae9f0462".to_string()
+        ]
+    );
+    assert_eq!(
+        registry_dir_names("sparse+https://index.crates.io/"),
+        vec![
+            "index.crates.io-6f17d22bba15001f".to_string(),
+            "index.crates.io-1949cf8c6b5b557f".to_string()
+        ]
+    );
+    let internal_url = "https://github.com/example/internal-index";
+    let internal = registry_dir_names(internal_url).remove(0);
+    let mut cargo_config = CargoConfig::new();
+    cargo_config.add_registry("internal", internal_url);
+    assert_eq!(cargo_config.url(&internal), Some(internal_url));
+    assert_eq!(
+        cargo_config.url("github.com-1ecc6299db9ec823"),
+        Some("https://github.com/rust-lang/crates.io-index")
+    );
+
+    let cargo_home = TempDir::new("registry-identity");
+    for registry in &["github.com-1ecc6299db9ec823", internal.as_str()] {
+        let src_dir = cargo_home.join("registry").join("src").join(registry);
+        std::fs::create_dir_all(src_dir.join("foo-1.0.0")).unwrap();
+    }
+    let project = cargo_home.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("Cargo.toml"), "").unwrap();
+    std::fs::write(
+        project.join("Cargo.lock"),
+        format!(
+            "[[package]]\nname = \"foo\"\nversion = \"1.0.0\"\nsource = \
+             \"registry+{internal_url}\"\n"
+        ),
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_directory(project.to_str().unwrap());
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    assert!(errors.is_empty());
+    assert_eq!(
+        crate_list.used_registry(),
+        &vec![CrateId::parse("foo-1.0.0", &internal).unwrap()]
+    );
+}
+
+// test registry filter selects only registry whose index url hashes to
+// directory name even when other registry is served from same host
+#[test]
+fn test_registry_filter_identity() {
+    let internal_url = "https://github.com/example/internal-index";
+    let internal = registry_dir_names(internal_url).remove(0);
+    let crates_io = "github.com-1ecc6299db9ec823";
+    let mut cargo_config = CargoConfig::new();
+    cargo_config.add_registry("internal", internal_url);
+    assert!(cargo_config.matches(&internal, "internal"));
+    assert!(cargo_config.matches(&internal, internal_url));
+    assert!(!cargo_config.matches(crates_io, "internal"));
+    assert!(!cargo_config.matches(crates_io, internal_url));
+    assert!(!cargo_config.matches(&internal, "crates-io"));
+    assert!(cargo_config.matches(crates_io, "crates-io"));
+    // directory whose hash is not known is still matched by host
+    assert!(cargo_config.matches("github.com-0123456789abcdef", "internal"));
+
+    let cargo_home = TempDir::new("registry-filter-identity");
+    for registry in &[crates_io, internal.as_str()] {
+        let src_dir = cargo_home.join("registry").join("src").join(registry);
+        let cache_dir = cargo_home.join("registry").join("cache").join(registry);
+        std::fs::create_dir_all(src_dir.join("foo-1.0.0")).unwrap();
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        std::fs::write(cache_dir.join("foo-1.0.0.crate"), "crate").unwrap();
+    }
+    let (dir_path, mut crate_list, crate_detail, errors) =
+        cargo_home.create_list(&ConfigFile::new());
+    crate_list.filter_registry(|registry| cargo_config.matches(registry, "internal"));
+    assert!(errors.is_empty());
+    assert_eq!(
+        crate_list.installed_registry(),
+        &vec![CrateId::parse("foo-1.0.0", &internal).unwrap()]
+    );
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    removal_plan.add_all(&CrateFilter::new(&ConfigFile::new()).unwrap(), true, false);
+    removal_plan.filter_registry(|registry| cargo_config.matches(registry, "internal"));
+    assert_eq!(removal_plan.entries().count(), 2);
+    assert!(removal_plan
+        .entries()
+        .all(|entry| entry.path().to_string_lossy().contains(&internal)));
+}
+
+// test sparse index is detected and its cache entry of crate which is not
+// installed is removed
+#[test]
+fn test_sparse_index() {
+    let cargo_home = TempDir::new("sparse-index");
+    let index_dir = cargo_home.join("registry").join("index");
+    let git_index = index_dir.join("github.com-1ecc6299db9ec823");
+    let sparse_index = index_dir.join("index.crates.io-6f17d22bba15001f");
+    std::fs::create_dir_all(git_index.join(".git")).unwrap();
+    std::fs::create_dir_all(sparse_index.join(".cache").join("se").join("rd")).unwrap();
+    std::fs::create_dir_all(sparse_index.join(".cache").join("3").join("f")).unwrap();
+    std::fs::write(sparse_index.join(".cache").join("config.json"), "{}").unwrap();
+    let serde_cache = sparse_index
+        .join(".cache")
+        .join("se")
+        .join("rd")
+        .join("serde");
+    std::fs::write(&serde_cache, "serde").unwrap();
+    let foo_cache = sparse_index.join(".cache").join("3").join("f").join("foo");
+    std::fs::write(&foo_cache, "foo").unwrap();
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("index.crates.io-6f17d22bba15001f");
+    std::fs::create_dir_all(src_dir.join("serde-1.0.1")).unwrap();
+
+    let registry_index = RegistryIndex::list(&index_dir).unwrap();
+    assert_eq!(registry_index.len(), 2);
+    assert_eq!(registry_index[0].kind(), IndexKind::Git);
+    assert_eq!(registry_index[1].kind(), IndexKind::Sparse);
+    assert_eq!(registry_index[1].cache_path("Serde"), serde_cache);
+    assert_eq!(
+        registry_index[1].cache_entries(),
+        vec![
+            ("foo".to_string(), foo_cache.clone(), 3),
+            ("serde".to_string(), serde_cache.clone(), 5)
+        ]
+    );
+
+    let config_file = ConfigFile::new();
+    let (dir_path, crate_list, crate_detail, mut errors) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    removal_plan.add_old(&CrateFilter::new(&config_file).unwrap(), true, true);
+    removal_plan.add_all(&CrateFilter::new(&config_file).unwrap(), true, false);
+    let removed = removal_plan.execute(false, None, &mut errors);
+    let removed_path = removed
+        .iter()
+        .map(|entry| entry.path().clone())
+        .collect::<Vec<_>>();
+    let config_json_exists = sparse_index.join(".cache").join("config.json").exists();
+    assert!(errors.is_empty());
+    assert!(removed_path.contains(&foo_cache));
+    assert!(removed_path.contains(&serde_cache));
+    assert!(config_json_exists);
+}
+
+// test git db rev is resolved from refs and compressed repository is still
+// readable
+#[test]
+fn test_git_repository() {
+    let repo_path = TempDir::new("git-repository");
+    let repo = git2::Repository::init_bare(&repo_path).unwrap();
+    let signature = git2::Signature::now("cargo-trim", "cargo-trim@example.com").unwrap();
+    let tree_id = {
+        let mut tree_builder = repo.treebuilder(None).unwrap();
+        let blob_id = repo.blob(b"cargo-trim").unwrap();
+        tree_builder
+            .insert("README.md", blob_id, 0o100_644)
+            .unwrap();
+        tree_builder.write().unwrap()
+    };
+    let tree = repo.find_tree(tree_id).unwrap();
+    let first = repo
+        .commit(None, &signature, &signature, "first", &tree, &[])
+        .unwrap();
+    let first = repo.find_commit(first).unwrap();
+    let second = repo
+        .commit(None, &signature, &signature, "second", &tree, &[&first])
+        .unwrap();
+    // cargo db only contains remote branch and HEAD points to missing branch
+    repo.reference("refs/remotes/origin/main", second, false, "fetch")
+        .unwrap();
+    repo.reference_symbolic(
+        "refs/remotes/origin/HEAD",
+        "refs/remotes/origin/main",
+        false,
+        "fetch",
+    )
+    .unwrap();
+    repo.tag("v1", first.as_object(), &signature, "v1", false)
+        .unwrap();
+
+    assert_eq!(latest_rev(&repo_path, None).unwrap(), second.to_string());
+    assert_eq!(
+        latest_rev(&repo_path, Some("main")).unwrap(),
+        second.to_string()
+    );
+    assert_eq!(
+        latest_rev(&repo_path, Some("v1")).unwrap(),
+        first.id().to_string()
+    );
+    assert!(latest_rev(&repo_path, Some("missing")).is_err());
+
+    git_compress(&repo_path).unwrap();
+    let loose_objects = std::fs::read_dir(repo_path.join("objects"))
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().len() == 2)
+        .count();
+    let repo = git2::Repository::open_bare(&repo_path).unwrap();
+    let tag_found = repo.revparse_single("v1").is_ok();
+    let second_found = repo.find_commit(second).is_ok();
+    let head_rev = latest_rev(&repo_path, None).unwrap();
+    assert_eq!(loose_objects, 0);
+    assert!(tag_found);
+    assert!(second_found);
+    assert_eq!(head_rev, second.to_string());
+
+    // multi pack index and pack list of old pack are not left behind
+    let pack_dir = repo_path.join("objects").join("pack");
+    let info_packs = repo_path.join("objects").join("info").join("packs");
+    let tree = repo.find_tree(tree_id).unwrap();
+    let second = repo.find_commit(second).unwrap();
+    let third = repo
+        .commit(None, &signature, &signature, "third", &tree, &[&second])
+        .unwrap();
+    repo.reference("refs/remotes/origin/main", third, true, "fetch")
+        .unwrap();
+    std::fs::write(pack_dir.join("multi-pack-index"), "MIDX").unwrap();
+    std::fs::create_dir_all(info_packs.parent().unwrap()).unwrap();
+    std::fs::write(&info_packs, "P pack-old.pack\n\n").unwrap();
+    let summary = git_compress(&repo_path).unwrap();
+    let packs = std::fs::read_dir(&pack_dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.to_lowercase().ends_with(".pack"))
+        .collect::<Vec<_>>();
+    let repo = git2::Repository::open_bare(&repo_path).unwrap();
+    let third_found = repo.find_commit(third).is_ok();
+    assert_eq!(summary.removed_packs(), 1);
+    assert_eq!(summary.packed_objects(), 6);
+    assert_eq!(packs.len(), 1);
+    assert!(!pack_dir.join("multi-pack-index").exists());
+    assert_eq!(
+        std::fs::read_to_string(&info_packs).unwrap(),
+        format!("P {}\n\n", packs[0])
+    );
+    assert!(third_found);
+}
+
+// test checkout of git db whose rev cannot be resolved is not listed as orphan
+// or old
+#[test]
+fn test_unresolved_git() {
+    let cargo_home = TempDir::new("unresolved-git");
+    let git_dir = cargo_home.join("git");
+    std::fs::create_dir_all(git_dir.join("db").join("foo-1ecc6299db9ec823")).unwrap();
+    for rev in &["1234567", "89abcde"] {
+        std::fs::create_dir_all(
+            git_dir
+                .join("checkouts")
+                .join("foo-1ecc6299db9ec823")
+                .join(rev),
+        )
+        .unwrap();
+    }
+    let config_file = ConfigFile::new();
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(crate_list.installed_git().len(), 3);
+    assert!(crate_list.orphan_git().is_empty());
+    assert!(crate_list.old_git().is_empty());
+}
+
+// test git crate of Cargo.lock is matched with git db which contains its commit
+// and checkout whose revision is prefix of commit
+#[test]
+fn test_git_commit_match() {
+    let cargo_home = TempDir::new("git-commit-match");
+    let git_dir = cargo_home.join("git");
+    let init_db = |db: &str, message: &str| {
+        let repo = git2::Repository::init_bare(git_dir.join("db").join(db)).unwrap();
+        let signature = git2::Signature::now("cargo-trim", "cargo-trim@example.com").unwrap();
+        let tree_id = repo.treebuilder(None).unwrap().write().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[])
+            .unwrap()
+            .to_string()
+    };
+    let serde_commit = init_db("serde-1111111111111111", "serde");
+    let fork_commit = init_db("serde-2222222222222222", "serde fork");
+    let serde_json_commit = init_db("serde_json-3333333333333333", "serde_json");
+    let checkouts = [
+        ("serde-1111111111111111", &serde_commit[..7]),
+        ("serde-1111111111111111", "abcdef012"),
+        ("serde-2222222222222222", &fork_commit[..9]),
+        ("serde_json-3333333333333333", &serde_json_commit[..7]),
+    ];
+    for (db, rev) in &checkouts {
+        std::fs::create_dir_all(git_dir.join("checkouts").join(db).join(rev)).unwrap();
+    }
+    let project = cargo_home.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("Cargo.toml"), "").unwrap();
+    std::fs::write(
+        project.join("Cargo.lock"),
+        format!(
+            "[[package]]\nname = \"serde\"\nversion = \"1.0.0\"\nsource = \
+             \"git+https://github.com/serde-rs/serde?branch=master#{serde_commit}\"\n"
+        ),
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_directory(project.to_str().unwrap());
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    assert!(errors.is_empty());
+    assert_eq!(
+        crate_list.used_git(),
+        &vec![
+            GitId::new("serde-1111111111111111", None),
+            GitId::new("serde-1111111111111111", Some(&serde_commit[..7])),
+        ]
+    );
+    assert_eq!(
+        crate_list.orphan_git(),
+        &vec![
+            GitId::new("serde-1111111111111111", Some("abcdef012")),
+            GitId::new("serde-2222222222222222", None),
+            GitId::new("serde-2222222222222222", Some(&fork_commit[..9])),
+            GitId::new("serde_json-3333333333333333", None),
+            GitId::new("serde_json-3333333333333333", Some(&serde_json_commit[..7])),
+        ]
+    );
+    assert_eq!(
+        crate_list.old_git(),
+        &vec![GitId::new("serde-1111111111111111", Some("abcdef012"))]
+    );
+}
+
+// test package cache lock held by other process is reported without waiting
+#[test]
+fn test_package_cache_lock() {
+    let cargo_home = TempDir::new("package-cache-lock");
+    let cargo_lock = File::create(cargo_home.join(".package-cache")).unwrap();
+    cargo_lock.lock().unwrap();
+
+    let mut package_cache_lock = PackageCacheLock::new(&cargo_home);
+    let locked = package_cache_lock.acquire(false);
+    let was_locked = package_cache_lock.is_locked();
+    cargo_lock.unlock().unwrap();
+    let acquired = package_cache_lock.acquire(false);
+    let is_locked = package_cache_lock.is_locked();
+    package_cache_lock.release();
+    let released = cargo_lock.try_lock().is_ok();
+    assert!(matches!(locked, Err(TrimError::Locked { .. })));
+    assert!(!was_locked);
+    assert!(acquired.is_ok());
+    assert!(is_locked);
+    assert!(released);
+}
+
+// test removed path moved to trash run can be restored and trash run can be
+// purged
+#[test]
+fn test_trash() {
+    let cargo_home = TempDir::new("trash");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    let cache_dir = cargo_home
+        .join("registry")
+        .join("cache")
+        .join("github.com-1ecc6299db9ec823");
+    std::fs::create_dir_all(src_dir.join("serde-1.0.1")).unwrap();
+    std::fs::write(src_dir.join("serde-1.0.1").join("lib.rs"), "serde").unwrap();
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    std::fs::write(cache_dir.join("serde-1.0.1.crate"), "crate").unwrap();
+
+    let config_file = ConfigFile::new();
+    let (dir_path, crate_list, crate_detail, mut errors) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    removal_plan.add_all(&CrateFilter::new(&config_file).unwrap(), true, false);
+    let mut trash_run = TrashRun::new(&dir_path);
+    let removed = removal_plan.execute(false, Some(&mut trash_run), &mut errors);
+    let removed_count = removed.len();
+    let source_exists = src_dir.join("serde-1.0.1").exists();
+    let trash = Trash::new(&dir_path);
+    let runs = trash.runs().unwrap();
+    let latest_id = trash.run(None).unwrap().map(|run| run.id().to_string());
+
+    let restored = trash.run(None).unwrap().unwrap().restore(&mut errors);
+    let source_restored = src_dir.join("serde-1.0.1").join("lib.rs").exists();
+    let archive_restored = cache_dir.join("serde-1.0.1.crate").exists();
+    let runs_after_restore = trash.runs().unwrap().len();
+
+    let mut second_run = TrashRun::new(&dir_path);
+    second_run
+        .move_path(&src_dir.join("serde-1.0.1"), 5)
+        .unwrap();
+    let kept = trash
+        .purge(Some(parse_duration("1h", "--older-than").unwrap()))
+        .unwrap();
+    let purged = trash.purge(None).unwrap();
+    let purged_source_exists = src_dir.join("serde-1.0.1").exists();
+    assert!(errors.is_empty());
+    assert_eq!(removed_count, 2);
+    assert!(!source_exists);
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].entries().len(), 2);
+    assert_eq!(runs[0].size(), 10);
+    assert_eq!(latest_id.as_deref(), Some(trash_run.id()));
+    assert_eq!(restored.len(), 2);
+    assert!(source_restored);
+    assert!(archive_restored);
+    assert_eq!(runs_after_restore, 0);
+    assert!(kept.is_empty());
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].size(), 5);
+    assert!(!purged_source_exists);
+}
+
+// test history entry is appended to history file and summary count size moved
+// to trash separately
+#[test]
+fn test_history() {
+    let history_dir = TempDir::new("history");
+    let history_file = history_dir.join("cargo_trim_history.jsonl");
+    let history = History::new(&history_file);
+    let mut errors = Vec::new();
+    let empty = history.entries(&mut errors).unwrap();
+
+    let mut old_clean = HistoryEntry::new(vec!["trim".to_string(), "-o".to_string()]);
+    old_clean.add(
+        &history_dir.join("serde-1.0.1.crate"),
+        "registry archive",
+        Some("serde-1.0.1"),
+        10,
+    );
+    old_clean.add(
+        &history_dir.join("serde-1.0.1"),
+        "registry source",
+        Some("serde-1.0.1"),
+        20,
+    );
+    let mut trash_clean = HistoryEntry::new(vec!["trim".to_string(), "--trash".to_string()]);
+    trash_clean.add(
+        &history_dir.join("serde_json-1.0.1"),
+        "registry source",
+        Some("serde_json-1.0.1"),
+        5,
+    );
+    trash_clean.set_trash_id("1");
+    let mut light_clean = HistoryEntry::new(vec!["trim".to_string(), "-l".to_string()]);
+    light_clean.add(&history_dir.join("src"), "registry source", None, 7);
+    history.append(&old_clean).unwrap();
+    history.append(&trash_clean).unwrap();
+    history.append(&light_clean).unwrap();
+    std::fs::write(
+        &history_file,
+        std::fs::read_to_string(&history_file).unwrap() + "invalid\n",
+    )
+    .unwrap();
+    let entries = history.entries(&mut errors).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].args(), &["trim", "-o"]);
+    assert_eq!(entries[0].total(), 30);
+    assert_eq!(entries[1].trash_id(), Some("1"));
+    assert!(entries[0].removed_crate("serde"));
+    assert!(entries[0].removed_crate("serde-1.0.1"));
+    assert!(!entries[1].removed_crate("serde"));
+    assert!(!entries[2].removed_crate("serde"));
+
+    let summary = HistorySummary::new(&entries);
+    assert_eq!(summary.runs(), 3);
+    assert_eq!(summary.freed(), 37);
+    assert_eq!(summary.quarantined(), 5);
+    assert_eq!(summary.categories().get("registry archive"), Some(&10));
+    assert_eq!(summary.categories().get("registry source"), Some(&27));
+}
+
+// test include and exclude rules support glob pattern and regular expression
+#[test]
+fn test_crate_filter() {
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("tokio*");
+    config_file.add_exclude("re:^serde(_json)?$");
+    config_file.add_exclude("libc-0.2.1");
+    config_file.add_include("winapi-*-pc-windows-gnu");
+    config_file.add_include("tokio-macros");
+    let crate_filter = CrateFilter::new(&config_file).unwrap();
+    let kinds = crate_filter
+        .exclude()
+        .iter()
+        .map(CrateRule::kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![RuleKind::Glob, RuleKind::Regex, RuleKind::Exact]
+    );
+    assert_eq!(crate_filter.exclude()[0].source(), RuleSource::ConfigFile);
+
+    assert!(crate_filter.is_excluded("tokio-1.0.0", "tokio", None));
+    assert!(crate_filter.is_excluded("tokio-util-0.7.0", "tokio-util", None));
+    assert!(crate_filter.is_excluded("serde_json-1.0.0", "serde_json", None));
+    assert!(!crate_filter.is_excluded("serde_derive-1.0.0", "serde_derive", None));
+    assert!(crate_filter.is_excluded("libc-0.2.1", "libc", None));
+    assert!(!crate_filter.is_excluded("libc-0.2.2", "libc", None));
+    assert_eq!(
+        crate_filter.exclude()[1].matches("serde-1.0.1", "serde", None),
+        Some("serde")
+    );
+    assert_eq!(
+        crate_filter
+            .included_by(
+                "winapi-x86_64-pc-windows-gnu-0.4.0",
+                "winapi-x86_64-pc-windows-gnu",
+                None
+            )
+            .map(CrateRule::value),
+        Some("winapi-*-pc-windows-gnu")
+    );
+    assert_eq!(crate_filter.mode(), FilterMode::Denylist);
+    assert!(!crate_filter.follow("tokio-1.0.0", "tokio", None));
+    // exclude rule takes priority over include rule
+    assert!(!crate_filter.follow("tokio-macros-1.0.0", "tokio-macros", None));
+    assert!(crate_filter.follow("rand-0.8.0", "rand", None));
+
+    config_file.set_mode(FilterMode::Allowlist);
+    let crate_filter = CrateFilter::new(&config_file).unwrap();
+    assert!(!crate_filter.follow("rand-0.8.0", "rand", None));
+    assert!(!crate_filter.follow("tokio-macros-1.0.0", "tokio-macros", None));
+    assert!(crate_filter.follow(
+        "winapi-x86_64-pc-windows-gnu-0.4.0",
+        "winapi-x86_64-pc-windows-gnu",
+        None
+    ));
+
+    for invalid in &["re:serde(", "tokio[*"] {
+        assert!(matches!(
+            CrateRule::parse(invalid, RuleSource::ConfigFile, "--exclude"),
+            Err(TrimError::InvalidValue { .. })
+        ));
+    }
+}
+
+// test include and exclude rules with semver requirement only match registry
+// crate whose version satisfies requirement and protect crate from old clean
+#[test]
+fn test_crate_filter_version() {
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("serde@^1.0.2");
+    config_file.add_exclude("syn@>=1.0, <2");
+    config_file.add_include("openssl-sys@<0.9.50");
+    config_file.set_mode(FilterMode::Allowlist);
+    let crate_filter = CrateFilter::new(&config_file).unwrap();
+    let version = |version| semver::Version::parse(version).unwrap();
+    assert!(crate_filter.exclude()[0].version_req().is_some());
+    assert_eq!(crate_filter.exclude()[0].kind(), RuleKind::Exact);
+
+    assert!(crate_filter.is_excluded("serde-1.0.2", "serde", Some(&version("1.0.2"))));
+    assert!(!crate_filter.is_excluded("serde-1.0.1", "serde", Some(&version("1.0.1"))));
+    assert!(crate_filter.is_excluded("syn-1.0.109", "syn", Some(&version("1.0.109"))));
+    assert!(!crate_filter.is_excluded("syn-2.0.0", "syn", Some(&version("2.0.0"))));
+    // git crate doesn't have version so it is never matched
+    assert!(!crate_filter.is_excluded("serde-HEAD", "serde", None));
+    assert!(crate_filter.follow(
+        "openssl-sys-0.9.49",
+        "openssl-sys",
+        Some(&version("0.9.49"))
+    ));
+    assert!(!crate_filter.follow(
+        "openssl-sys-0.9.50",
+        "openssl-sys",
+        Some(&version("0.9.50"))
+    ));
+    // whitespace inside requirement of environment variable rule is not split
+    let rules = join_version_req(
+        "tokio syn@>=1.0, <2 serde@^1 re:^winapi"
+            .split_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    assert_eq!(
+        rules,
+        vec!["tokio", "syn@>=1.0, <2", "serde@^1", "re:^winapi"]
+    );
+    assert!(CrateRule::parse(
+        &rules[1],
+        RuleSource::Environment("TRIM_EXCLUDE"),
+        "--exclude"
+    )
+    .unwrap()
+    .version_req()
+    .is_some());
+    // `@` of regular expression is only treated as separator when it is
+    // followed by valid semver requirement
+    let rule = CrateRule::parse("re:^foo@.*", RuleSource::ConfigFile, "--exclude").unwrap();
+    assert_eq!(rule.kind(), RuleKind::Regex);
+    assert!(rule.version_req().is_none());
+    assert_eq!(rule.matches("foo@bar", "foo@bar", None), Some("foo@bar"));
+    assert_eq!(rule.matches("foo-1.0.0", "foo", None), None);
+    let rule = CrateRule::parse("re:^a@b$@^1", RuleSource::ConfigFile, "--exclude").unwrap();
+    assert!(rule.version_req().is_some());
+    assert_eq!(
+        rule.matches("a@b-1.0.0", "a@b", Some(&version("1.0.0"))),
+        Some("a@b")
+    );
+    for invalid in &["serde@^x", "serde@"] {
+        assert!(matches!(
+            CrateRule::parse(invalid, RuleSource::ConfigFile, "--exclude"),
+            Err(TrimError::InvalidValue { .. })
+        ));
+    }
+
+    let cargo_home = TempDir::new("crate-filter-version");
+    let registry = "github.com-1ecc6299db9ec823";
+    let src_dir = cargo_home.join("registry").join("src").join(registry);
+    for crate_name in &["serde-0.9.0", "serde-1.0.1", "serde-1.0.2", "serde-1.0.3"] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+        std::fs::write(src_dir.join(crate_name).join("lib.rs"), "").unwrap();
+    }
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("serde@>=1.0.1, <1.0.3");
+    let (dir_path, crate_list, crate_detail, errors) = cargo_home.create_list(&config_file);
+    let mut removal_plan = RemovalPlan::new(&dir_path, &crate_list, &crate_detail).unwrap();
+    removal_plan.add_old(&CrateFilter::new(&config_file).unwrap(), true, true);
+    let planned = removal_plan
+        .entries()
+        .map(|entry| entry.crate_name().to_string())
+        .collect::<Vec<_>>();
+    assert!(errors.is_empty());
+    assert_eq!(planned, vec!["serde-0.9.0".to_string()]);
+}
+
+// test project config file skips subtree while scanning for Cargo.toml and
+// protects crates from removal
+#[test]
+fn test_project_config() {
+    let cargo_home = TempDir::new("project-config");
+    let project = cargo_home.join("project");
+    for directory in &["app", "vendor/dep", "fixtures/a/b"] {
+        std::fs::create_dir_all(project.join(directory)).unwrap();
+        std::fs::write(project.join(directory).join("Cargo.toml"), "").unwrap();
+    }
+    std::fs::write(
+        project.join(".cargo-trim.toml"),
+        "protect = [\"serde@^1\", \"tokio\"]\nskip = [\"vendor\", \"fixtures/*\"]\n",
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_directory(project.to_str().unwrap());
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    let mut crate_filter = CrateFilter::new(&config_file).unwrap();
+    let cargo_toml_location = crate_list.cargo_toml_location();
+    crate_filter
+        .add_project_rules(cargo_toml_location.project_configs())
+        .unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(
+        cargo_toml_location.location_path(),
+        &vec![project.join("app")]
+    );
+    assert_eq!(cargo_toml_location.project_configs().len(), 1);
+    assert_eq!(
+        cargo_toml_location.project_configs()[0].path(),
+        &project.join(".cargo-trim.toml")
+    );
+    assert_eq!(crate_filter.exclude()[0].source(), RuleSource::ProjectFile);
+    assert!(crate_filter.is_excluded("tokio-1.0.0", "tokio", None));
+    assert!(!crate_filter.follow(
+        "serde-1.0.1",
+        "serde",
+        Some(&semver::Version::parse("1.0.1").unwrap())
+    ));
+    assert!(crate_filter.follow(
+        "serde-0.9.0",
+        "serde",
+        Some(&semver::Version::parse("0.9.0").unwrap())
+    ));
+}
+
+// test scanner honours ignore file, skips target and hidden directory, limits
+// depth, skips symlink cycle and reuses cache until directory is modified
+#[test]
+fn test_scanner() {
+    let root = TempDir::new("scanner");
+    let cache_dir = TempDir::new("scanner-cache");
+    let cache_file = cache_dir.join("cargo-trim-scanner-cache.json");
+    for directory in &[
+        "app",
+        "app/nested/deep",
+        "ignored",
+        "target/debug",
+        ".hidden",
+        "git_ignored",
+    ] {
+        std::fs::create_dir_all(root.join(directory)).unwrap();
+        std::fs::write(root.join(directory).join("Cargo.toml"), "").unwrap();
+    }
+    std::fs::write(root.join(".ignore"), "ignored\n").unwrap();
+    std::fs::write(root.join(".gitignore"), "git_ignored/\n").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&root, root.join("app").join("loop")).unwrap();
+    let directories = vec![root.to_string_lossy().to_string()];
+
+    let mut scanner = Scanner::new();
+    scanner.set_cache_file(&cache_file);
+    let mut errors = Vec::new();
+    let first_scan = scanner.scan(&directories, &mut errors);
+    let cache_exists = cache_file.exists();
+    let cached = scanner.scan(&directories, &mut errors);
+    std::fs::create_dir_all(root.join("added")).unwrap();
+    std::fs::write(root.join("added").join("Cargo.toml"), "").unwrap();
+    let modified_scan = scanner.scan(&directories, &mut errors);
+    scanner.set_max_depth(Some(1));
+    let limited = scanner.scan(&directories, &mut errors);
+
+    assert!(errors.is_empty());
+    assert!(cache_exists);
+    assert_eq!(
+        first_scan.location_path(),
+        &vec![root.join("app"), root.join("app/nested/deep")]
+    );
+    assert_eq!(cached.location_path(), first_scan.location_path());
+    assert_eq!(
+        modified_scan.location_path(),
+        &vec![
+            root.join("added"),
+            root.join("app"),
+            root.join("app/nested/deep")
+        ]
+    );
+    assert_eq!(
+        limited.location_path(),
+        &vec![root.join("added"), root.join("app")]
+    );
+}
+
+// test workspace members are resolved to workspace root and crates of root
+// Cargo.lock are recorded with workspace using them
+#[test]
+fn test_workspace() {
+    let cargo_home = TempDir::new("workspace");
+    let project = cargo_home.join("project");
+    let manifests = [
+        (
+            "ws",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n",
+        ),
+        ("ws/crates/app", "[package]\nname = \"app\"\n"),
+        ("ws/crates/lib", "[package]\nname = \"lib\"\n"),
+        ("ws/crates/excluded", "[package]\nname = \"excluded\"\n"),
+        (
+            "outside",
+            "[package]\nname = \"outside\"\nworkspace = \"../ws\"\n",
+        ),
+        ("standalone", "[package]\nname = \"standalone\"\n"),
+    ];
+    for (directory, manifest) in &manifests {
+        std::fs::create_dir_all(project.join(directory)).unwrap();
+        std::fs::write(project.join(directory).join("Cargo.toml"), manifest).unwrap();
+    }
+    let serde = |version: &str| {
+        format!(
+            "[[package]]\nname = \"serde\"\nversion = \"{version}\"\nsource = \
+             \"registry+https://github.com/rust-lang/crates.io-index\"\n"
+        )
+    };
+    std::fs::write(project.join("ws").join("Cargo.lock"), serde("1.0.1")).unwrap();
+    std::fs::write(
+        project.join("standalone").join("Cargo.lock"),
+        serde("1.0.1"),
+    )
+    .unwrap();
+    std::fs::write(
+        project.join("ws/crates/excluded").join("Cargo.lock"),
+        serde("1.0.2"),
+    )
+    .unwrap();
+    // lock file of member is never read since it is not used by cargo
+    std::fs::write(
+        project.join("ws/crates/app").join("Cargo.lock"),
+        serde("0.9.0"),
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_directory(project.to_str().unwrap());
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    assert!(errors.is_empty());
+    let workspaces = crate_list.cargo_toml_location().workspaces();
+    assert_eq!(
+        crate_list.cargo_toml_location().workspace_roots(),
+        vec![
+            project.join("standalone"),
+            project.join("ws"),
+            project.join("ws/crates/excluded"),
+        ]
+    );
+    assert_eq!(
+        workspaces[1].members(),
+        &[
+            project.join("outside"),
+            project.join("ws"),
+            project.join("ws/crates/app"),
+            project.join("ws/crates/lib"),
+        ]
+    );
+    let crate_id = |version: &str| {
+        CrateId::new(
+            "serde",
+            semver::Version::parse(version).unwrap(),
+            "github.com",
+        )
+    };
+    assert_eq!(
+        crate_list.used_registry(),
+        &vec![crate_id("1.0.1"), crate_id("1.0.2")]
+    );
+    assert_eq!(
+        crate_list.registry_workspaces(&crate_id("1.0.1")),
+        &[project.join("standalone"), project.join("ws")]
+    );
+    assert_eq!(
+        crate_list.registry_workspaces(&crate_id("1.0.2")),
+        &[project.join("ws/crates/excluded")]
+    );
+    assert!(crate_list
+        .registry_workspaces(&crate_id("0.9.0"))
+        .is_empty());
+}
+
+// test lock file of all versions is read along with v1 metadata checksum and
+// problem of lock file is reported as warning
+#[test]
+fn test_lock_file() {
+    let directory = TempDir::new("lock-file");
+    let crates_io = "registry+https://github.com/rust-lang/crates.io-index";
+    let lock_files = [
+        (
+            "v1.lock",
+            format!(
+                "[root]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\n \"serde 1.0.1 \
+                 ({crates_io})\",\n]\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\nsource \
+                 = \"{crates_io}\"\n\n[metadata]\n\"checksum serde 1.0.1 ({crates_io})\" = \"abc\"\n"
+            ),
+        ),
+        (
+            "v2.lock",
+            format!(
+                "[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\nsource = \"{crates_io}\"\nchecksum \
+                 = \"abc\"\n"
+            ),
+        ),
+        (
+            "v4.lock",
+            "version = 4\n\n[[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = \
+             [\"serde\"]\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\nsource = \
+             \"sparse+https://index.crates.io/\"\nchecksum = \"abc\"\n"
+                .to_string(),
+        ),
+        (
+            "v5.lock",
+            "version = 5\n\n[[package]]\nname = \"serde\"\n\n[[package]]\nname = \"app\"\nversion \
+             = \"0.1.0\"\n"
+                .to_string(),
+        ),
+        ("invalid.lock", "[[package]\n".to_string()),
+    ];
+    for (name, content) in &lock_files {
+        std::fs::write(directory.join(name), content).unwrap();
+    }
+    let mut warnings = Vec::new();
+    let read =
+        |name: &str, warnings: &mut Vec<TrimError>| LockFile::read(&directory.join(name), warnings);
+    let v1 = read("v1.lock", &mut warnings).unwrap();
+    let v2 = read("v2.lock", &mut warnings).unwrap();
+    let v4 = read("v4.lock", &mut warnings).unwrap();
+    let v1_warnings = warnings.len();
+    let v5 = read("v5.lock", &mut warnings).unwrap();
+    let invalid = read("invalid.lock", &mut warnings);
+
+    assert_eq!(v1_warnings, 0);
+    assert_eq!(v1.version(), 1);
+    assert_eq!(v1.packages().len(), 2);
+    assert_eq!(v1.packages()[0].name(), "app");
+    assert_eq!(v1.packages()[0].dependencies()[0].version(), Some("1.0.1"));
+    assert_eq!(v1.packages()[0].dependencies()[0].source(), Some(crates_io));
+    assert!(v1.packages()[0].dependencies()[0].matches(&v1.packages()[1]));
+    assert_eq!(v1.packages()[1].checksum(), Some("abc"));
+    assert_eq!(v2.version(), 2);
+    assert_eq!(v2.packages()[0].checksum(), Some("abc"));
+    assert_eq!(v4.version(), 4);
+    assert_eq!(
+        v4.packages()[1].source(),
+        Some("sparse+https://index.crates.io/")
+    );
+    assert!(v4.packages()[0].dependencies()[0].matches(&v4.packages()[1]));
+    assert_eq!(v5.version(), 5);
+    assert_eq!(v5.packages().len(), 1);
+    assert_eq!(warnings.len(), 2);
+    assert!(matches!(invalid, Err(TrimError::Toml { .. })));
+}
+
+// test lock file which cannot be read is reported as warning and sparse
+// registry crate is listed as used
+#[test]
+fn test_lock_file_warning() {
+    let cargo_home = TempDir::new("lock-file-warning");
+    let project = cargo_home.join("project");
+    for directory in &["sparse", "invalid"] {
+        std::fs::create_dir_all(project.join(directory)).unwrap();
+        std::fs::write(project.join(directory).join("Cargo.toml"), "").unwrap();
+    }
+    std::fs::write(
+        project.join("sparse").join("Cargo.lock"),
+        "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.1\"\nsource = \
+         \"sparse+https://index.crates.io/\"\n",
+    )
+    .unwrap();
+    std::fs::write(project.join("invalid").join("Cargo.lock"), "[[package]\n").unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_directory(project.to_str().unwrap());
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    assert!(errors.is_empty());
+    assert_eq!(crate_list.warnings().len(), 1);
+    assert_eq!(
+        crate_list.used_registry(),
+        &vec![CrateId::new(
+            "serde",
+            semver::Version::parse("1.0.1").unwrap(),
+            "index.crates.io"
+        )]
+    );
+}
+
+// test dependency path from workspace member to crate is shortest path and
+// package is matched by version and source of dependency
+#[test]
+fn test_dependency_paths() {
+    let directory = TempDir::new("dependency-paths");
+    let lock_file = directory.join("Cargo.lock");
+    std::fs::write(
+        &lock_file,
+        "version = 3\n\n[[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = \
+         [\"lib\", \"foo\", \"serde 0.9.0\"]\n\n[[package]]\nname = \"lib\"\nversion = \
+         \"0.1.0\"\ndependencies = [\"foo\"]\n\n[[package]]\nname = \"foo\"\nversion = \
+         \"0.2.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+         dependencies = [\"serde 1.0.1\"]\n\n[[package]]\nname = \"serde\"\nversion = \
+         \"1.0.1\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\nname = \"serde\"\nversion = \"0.9.0\"\nsource = \
+         \"registry+https://github.com/rust-lang/crates.io-index\"\n\n[[package]]\nname = \
+         \"unused\"\nversion = \"0.1.0\"\nsource = \
+         \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+    let mut warnings = Vec::new();
+    let lock_file = LockFile::read(&lock_file, &mut warnings).unwrap();
+    let paths = |name: &str| {
+        lock_file
+            .dependency_paths(|package| package.name() == name)
+            .iter()
+            .map(|path| {
+                path.iter()
+                    .map(|package| format!("{} {}", package.name(), package.version()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(warnings.is_empty());
+    assert_eq!(
+        paths("serde"),
+        vec![
+            vec!["app 0.1.0", "foo 0.2.0", "serde 1.0.1"],
+            vec!["app 0.1.0", "serde 0.9.0"],
+        ]
+    );
+    assert_eq!(paths("lib"), vec![vec!["app 0.1.0", "lib 0.1.0"]]);
+    assert_eq!(paths("app"), vec![vec!["app 0.1.0"]]);
+    assert!(paths("unused").is_empty());
+}
+
+// test exclude rule protecting installed crate is found even if no lock file
+// uses crate and git checkout is matched by its repo name and full revision
+#[test]
+fn test_why_protection() {
+    let cargo_home = TempDir::new("why-protection");
+    let src_dir = cargo_home
+        .join("registry")
+        .join("src")
+        .join("github.com-1ecc6299db9ec823");
+    for crate_name in &["libc-0.2.1", "libc-0.2.2"] {
+        std::fs::create_dir_all(src_dir.join(crate_name)).unwrap();
+    }
+    let git_dir = cargo_home.join("git");
+    let repo =
+        git2::Repository::init_bare(git_dir.join("db").join("serde-1111111111111111")).unwrap();
+    let signature = git2::Signature::now("cargo-trim", "cargo-trim@example.com").unwrap();
+    let tree_id = repo.treebuilder(None).unwrap().write().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &signature, &signature, "serde", &tree, &[])
+        .unwrap()
+        .to_string();
+    let checkout = GitId::new("serde-1111111111111111", Some(&commit[..9]));
+    std::fs::create_dir_all(
+        git_dir
+            .join("checkouts")
+            .join(checkout.db())
+            .join(checkout.rev().unwrap()),
+    )
+    .unwrap();
+    let mut config_file = ConfigFile::new();
+    config_file.add_exclude("libc@=0.2.1");
+    config_file.add_exclude(&format!("serde-{}", &commit[..9]));
+    let (_, crate_list, _, errors) = cargo_home.create_list(&config_file);
+    let crate_filter = CrateFilter::new(&config_file).unwrap();
+    assert!(errors.is_empty());
+    assert!(crate_list.lock_files().is_empty());
+
+    let libc = crate_list.installed_registry_named("libc", None);
+    assert_eq!(libc.len(), 2);
+    assert_eq!(
+        crate_filter
+            .registry_excluded_by(libc[0])
+            .map(CrateRule::value),
+        Some("libc@=0.2.1")
+    );
+    assert!(crate_filter.registry_excluded_by(libc[1]).is_none());
+    let version = semver::Version::parse("0.2.2").unwrap();
+    assert_eq!(
+        crate_list.installed_registry_named("libc", Some(&version)),
+        vec![libc[1]]
+    );
+
+    let serde = crate_list.installed_git_named("serde");
+    assert_eq!(
+        serde,
+        vec![&GitId::new("serde-1111111111111111", None), &checkout]
+    );
+    assert!(crate_filter.git_excluded_by(serde[0]).is_none());
+    assert!(crate_filter.git_excluded_by(&checkout).is_some());
+    assert!(!crate_filter.follow_git(&checkout));
+}
+
+// test size of crates used by project directory and size used only by it
+#[test]
+fn test_project_usage() {
+    let cargo_home = TempDir::new("project-usage");
+    let registry = "github.com-1ecc6299db9ec823";
+    let cache_dir = cargo_home.join("registry").join("cache").join(registry);
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    for (crate_name, content) in &[
+        ("serde-1.0.1", "12345"),
+        ("libc-0.2.1", "123"),
+        ("log-0.4.0", "1"),
+    ] {
+        std::fs::write(cache_dir.join(format!("{crate_name}.crate")), content).unwrap();
+    }
+    let package = |name: &str, version: &str| {
+        format!(
+            "[[package]]\nname = \"{name}\"\nversion = \"{version}\"\nsource = \
+             \"registry+https://github.com/rust-lang/crates.io-index\"\n"
+        )
+    };
+    let projects = [
+        (
+            "first",
+            package("serde", "1.0.1") + &package("libc", "0.2.1"),
+        ),
+        (
+            "second",
+            package("libc", "0.2.1") + &package("log", "0.4.0"),
+        ),
+        ("empty", String::new()),
+    ];
+    let mut config_file = ConfigFile::new();
+    for (project, lock_file) in &projects {
+        let directory = cargo_home.join(project);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("Cargo.toml"), "").unwrap();
+        std::fs::write(directory.join("Cargo.lock"), lock_file).unwrap();
+        config_file.add_directory(directory.to_str().unwrap());
+    }
+    let (_, crate_list, crate_detail, errors) = cargo_home.create_list(&config_file);
+    let usage = crate_list.project_usage(&crate_detail);
+    assert!(errors.is_empty());
+    let sizes = usage
+        .iter()
+        .map(|project| {
+            (
+                project.directory().clone(),
+                project.registry_size(),
+                project.registry_exclusive_size(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sizes,
+        vec![
+            (cargo_home.join("empty"), 0, 0),
+            (cargo_home.join("first"), 8, 5),
+            (cargo_home.join("second"), 4, 1),
+        ]
+    );
+}
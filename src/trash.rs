@@ -0,0 +1,285 @@
+use crate::{
+    error::{io_error, TrimError},
+    utils::{read_dir, unix_time},
+    DirPath,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// name of manifest file present inside directory of each trash run
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Single path which is moved to trash
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    original: PathBuf,
+    stored: PathBuf,
+    size: u64,
+}
+
+impl TrashEntry {
+    /// return path from where entry was moved
+    #[must_use]
+    pub fn original(&self) -> &PathBuf {
+        &self.original
+    }
+
+    /// return size of entry in bytes
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    created: u64,
+    entries: Vec<TrashEntry>,
+}
+
+/// Single invocation of cargo-trim in trash mode. All path removed by run are
+/// moved to `.trim-trash/<id>` directory of cargo home along with manifest
+/// which records their original location so run can be restored
+pub struct TrashRun {
+    id: String,
+    path: PathBuf,
+    cargo_home: PathBuf,
+    created: SystemTime,
+    entries: Vec<TrashEntry>,
+}
+
+impl TrashRun {
+    /// create new run inside trash directory, id of run is timestamp of its
+    /// creation and directory of run is only created once first path is moved
+    #[must_use]
+    pub fn new(dir_path: &DirPath) -> Self {
+        let created = SystemTime::now();
+        let timestamp = unix_time(created);
+        let mut id = timestamp.to_string();
+        let mut suffix = 1;
+        while dir_path.trash_dir().join(&id).exists() {
+            id = format!("{timestamp}-{suffix}");
+            suffix += 1;
+        }
+        Self {
+            path: dir_path.trash_dir().join(&id),
+            id,
+            cargo_home: dir_path.cargo_home().clone(),
+            created,
+            entries: Vec::new(),
+        }
+    }
+
+    // load run from its directory by reading its manifest
+    fn load(path: &Path, cargo_home: &Path) -> Result<Self, TrimError> {
+        let manifest_path = path.join(MANIFEST_FILE);
+        let buffer = fs::read_to_string(&manifest_path)
+            .map_err(io_error(&manifest_path, "read trash manifest"))?;
+        let manifest =
+            serde_json::from_str::<Manifest>(&buffer).map_err(|source| TrimError::Json {
+                path: manifest_path,
+                source,
+            })?;
+        Ok(Self {
+            id: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            cargo_home: cargo_home.to_path_buf(),
+            created: UNIX_EPOCH + Duration::from_secs(manifest.created),
+            entries: manifest.entries,
+        })
+    }
+
+    /// return id of run
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// return time at which run was created
+    #[must_use]
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// return all entries moved to trash by run
+    #[must_use]
+    pub fn entries(&self) -> &[TrashEntry] {
+        &self.entries
+    }
+
+    /// return total size of all entries of run in bytes
+    pub fn size(&self) -> u64 {
+        self.entries.iter().map(TrashEntry::size).sum()
+    }
+
+    /// move path to trash, path is stored at same location relative to cargo
+    /// home inside directory of run. Manifest is saved after every move so run
+    /// can be restored even when cargo-trim is interrupted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if path cannot be moved to trash or manifest of run
+    /// cannot be saved
+    pub fn move_path(&mut self, path: &Path, size: u64) -> Result<(), TrimError> {
+        let relative = path.strip_prefix(&self.cargo_home).map_or_else(
+            |_| PathBuf::from(path.file_name().unwrap_or_default()),
+            Path::to_path_buf,
+        );
+        // parent of already moved path can be moved later by same run such as
+        // registry being wiped after its crate is removed so stored path is
+        // made unique
+        let mut stored = relative.clone();
+        let mut suffix = 1;
+        while self.path.join(&stored).exists() {
+            stored = PathBuf::from(format!("{}.{suffix}", relative.display()));
+            suffix += 1;
+        }
+        let stored_path = self.path.join(&stored);
+        if let Some(parent) = stored_path.parent() {
+            fs::create_dir_all(parent).map_err(io_error(parent, "create directory"))?;
+        }
+        fs::rename(path, &stored_path).map_err(io_error(path, "move to trash"))?;
+        self.entries.push(TrashEntry {
+            original: path.to_path_buf(),
+            stored,
+            size,
+        });
+        self.save()
+    }
+
+    /// restore all entries of run to their original location in reverse order
+    /// of their removal. Entry whose original location is already present is
+    /// not restored and its error is pushed to errors, directory of run is
+    /// removed once all entries are restored
+    pub fn restore(mut self, errors: &mut Vec<TrimError>) -> Vec<TrashEntry> {
+        let mut restored = Vec::new();
+        let mut remaining = Vec::new();
+        while let Some(entry) = self.entries.pop() {
+            match self.restore_entry(&entry) {
+                Ok(()) => restored.push(entry),
+                Err(e) => {
+                    errors.push(e);
+                    remaining.push(entry);
+                }
+            }
+        }
+        remaining.reverse();
+        self.entries = remaining;
+        let result = if self.entries.is_empty() {
+            fs::remove_dir_all(&self.path).map_err(io_error(&self.path, "remove directory"))
+        } else {
+            self.save()
+        };
+        if let Err(e) = result {
+            errors.push(e);
+        }
+        restored
+    }
+
+    // restore single entry to its original location
+    fn restore_entry(&self, entry: &TrashEntry) -> Result<(), TrimError> {
+        if entry.original.exists() {
+            return Err(TrimError::Io {
+                path: entry.original.clone(),
+                operation: "restore",
+                source: io::Error::new(io::ErrorKind::AlreadyExists, "path already exists"),
+            });
+        }
+        if let Some(parent) = entry.original.parent() {
+            fs::create_dir_all(parent).map_err(io_error(parent, "create directory"))?;
+        }
+        let stored_path = self.path.join(&entry.stored);
+        fs::rename(&stored_path, &entry.original).map_err(io_error(&stored_path, "restore"))
+    }
+
+    // save manifest of run
+    fn save(&self) -> Result<(), TrimError> {
+        let manifest_path = self.path.join(MANIFEST_FILE);
+        let manifest = Manifest {
+            created: unix_time(self.created),
+            entries: self.entries.clone(),
+        };
+        let buffer = serde_json::to_string_pretty(&manifest).map_err(|source| TrimError::Json {
+            path: manifest_path.clone(),
+            source,
+        })?;
+        fs::write(&manifest_path, buffer).map_err(io_error(&manifest_path, "write trash manifest"))
+    }
+}
+
+/// Trash directory of cargo home which contains all run of cargo-trim executed
+/// in trash mode
+pub struct Trash {
+    path: PathBuf,
+    cargo_home: PathBuf,
+}
+
+impl Trash {
+    /// create new `Trash` for trash directory of cargo home
+    #[must_use]
+    pub fn new(dir_path: &DirPath) -> Self {
+        Self {
+            path: dir_path.trash_dir().clone(),
+            cargo_home: dir_path.cargo_home().clone(),
+        }
+    }
+
+    /// list out all run present in trash sorted from oldest to newest
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if trash directory or manifest of run cannot be read
+    pub fn runs(&self) -> Result<Vec<TrashRun>, TrimError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for path in read_dir(&self.path)? {
+            // directory without manifest doesn't contain any moved path
+            if path.join(MANIFEST_FILE).is_file() {
+                runs.push(TrashRun::load(&path, &self.cargo_home)?);
+            }
+        }
+        runs.sort_by(|a, b| a.created.cmp(&b.created).then_with(|| a.id.cmp(&b.id)));
+        Ok(runs)
+    }
+
+    /// find run with provided id or latest run when id is not provided
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if trash directory or manifest of run cannot be read
+    pub fn run(&self, id: Option<&str>) -> Result<Option<TrashRun>, TrimError> {
+        let mut runs = self.runs()?;
+        Ok(match id {
+            Some(id) => runs.into_iter().find(|run| run.id == id),
+            None => runs.pop(),
+        })
+    }
+
+    /// permanently remove all run older than provided duration or all run when
+    /// duration is not provided, return list of removed run
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if trash cannot be read or run cannot be removed
+    pub fn purge(&self, older_than: Option<Duration>) -> Result<Vec<TrashRun>, TrimError> {
+        let now = SystemTime::now();
+        let mut purged = Vec::new();
+        for run in self.runs()? {
+            let age = now.duration_since(run.created).unwrap_or_default();
+            if older_than.is_none_or(|older_than| age > older_than) {
+                fs::remove_dir_all(&run.path).map_err(io_error(&run.path, "remove directory"))?;
+                purged.push(run);
+            }
+        }
+        Ok(purged)
+    }
+}
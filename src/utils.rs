@@ -0,0 +1,232 @@
+use crate::{
+    error::{io_error, TrimError},
+    trash::TrashRun,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// delete folder with folder path provided, when trash run is provided folder
+/// is moved to trash instead. Return size of folder which is removed or would
+/// be removed on dry run, none is returned when folder doesn't exist
+///
+/// # Errors
+///
+/// Returns an error if folder cannot be removed or moved to trash
+pub fn delete_folder(
+    path: &Path,
+    dry_run: bool,
+    trash_run: Option<&mut TrashRun>,
+) -> Result<Option<u64>, TrimError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let size = get_size(path).unwrap_or(0);
+    if dry_run {
+        return Ok(Some(size));
+    }
+    if let Some(trash_run) = trash_run {
+        trash_run.move_path(path, size)?;
+    } else {
+        fs::remove_dir_all(path).map_err(io_error(path, "remove directory"))?;
+    }
+    Ok(Some(size))
+}
+
+/// delete .cache folder of all registry present inside index directory, when
+/// trash run is provided folder is moved to trash instead. Return each removed
+/// folder along with its size
+///
+/// # Errors
+///
+/// Returns an error if index directory cannot be read or folder cannot be
+/// removed
+pub fn delete_index_cache(
+    index_dir: &Path,
+    dry_run: bool,
+    mut trash_run: Option<&mut TrashRun>,
+) -> Result<Vec<(PathBuf, u64)>, TrimError> {
+    let mut removed = Vec::new();
+    if index_dir.exists() {
+        for registry_dir in read_dir(index_dir)? {
+            for folder in read_dir(&registry_dir)? {
+                if file_name(&folder) == ".cache" {
+                    if let Some(size) = delete_folder(&folder, dry_run, trash_run.as_deref_mut())? {
+                        removed.push((folder, size));
+                    }
+                }
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// update Cargo.lock file of project directory, lock file is generated first
+/// when project doesn't have one
+///
+/// # Errors
+///
+/// Returns an error if cargo fails to generate or update lock file
+pub fn update_cargo_lock(location: &Path) -> Result<(), TrimError> {
+    if !location.join("Cargo.lock").exists() {
+        run_command("cargo", &["generate-lockfile"], location)?;
+    }
+    run_command("cargo", &["update"], location).map(|_| ())
+}
+
+/// parse duration such as `30d` or `12w` passed to argument, supported units
+/// are `s`, `m`, `h`, `d` and `w`
+///
+/// # Errors
+///
+/// Returns an error naming argument if value is not valid duration
+pub fn parse_duration(value: &str, argument: &'static str) -> Result<Duration, TrimError> {
+    let invalid_value = || TrimError::InvalidValue {
+        argument,
+        value: value.to_string(),
+    };
+    let value = value.trim();
+    let unit_position = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid_value)?;
+    let (number, unit) = value.split_at(unit_position);
+    let number = number.parse::<u64>().map_err(|_| invalid_value())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid_value()),
+    };
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid_value)
+}
+
+/// parse size such as `500MB` or `2GiB` to bytes, supported units are `B`,
+/// `KB`, `MB`, `GB`, `TB` and their binary variant `KiB`, `MiB`, `GiB`, `TiB`.
+/// Value without unit is treated as bytes
+///
+/// # Errors
+///
+/// Returns an error if value is not valid size
+pub fn parse_size(value: &str) -> Result<u64, TrimError> {
+    let invalid_value = || TrimError::InvalidValue {
+        argument: "--budget",
+        value: value.to_string(),
+    };
+    let value = value.trim();
+    let unit_position = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_position);
+    let number = number.parse::<f64>().map_err(|_| invalid_value())?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "kib" => 1024,
+        "mib" => 1024_u64.pow(2),
+        "gib" => 1024_u64.pow(3),
+        "tib" => 1024_u64.pow(4),
+        _ => return Err(invalid_value()),
+    };
+    let size = number * multiplier as f64;
+    if size.is_finite() && size >= 0.0 && size <= u64::MAX as f64 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(size as u64)
+    } else {
+        Err(invalid_value())
+    }
+}
+
+// run command with arguments inside provided directory, command which exits
+// with non zero status is treated as error with its stderr as message
+pub(crate) fn run_command(
+    program: &str,
+    args: &[&str],
+    current_dir: &Path,
+) -> Result<Output, TrimError> {
+    let command_error = |source| TrimError::Command {
+        command: format!("{} {}", program, args.join(" ")),
+        path: current_dir.to_path_buf(),
+        source,
+    };
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(current_dir)
+        .output()
+        .map_err(command_error)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(command_error(io::Error::other(format!(
+            "{}: {}",
+            output.status,
+            stderr.trim()
+        ))));
+    }
+    Ok(output)
+}
+
+// list out path of all entries present inside directory
+pub(crate) fn read_dir(path: &Path) -> Result<Vec<PathBuf>, TrimError> {
+    fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .map_err(io_error(path, "read directory"))
+}
+
+// get size of file or directory
+pub(crate) fn get_size(path: &Path) -> Result<u64, TrimError> {
+    fs_extra::dir::get_size(path).map_err(|e| TrimError::Io {
+        path: path.to_path_buf(),
+        operation: "get size of",
+        source: io::Error::other(e.to_string()),
+    })
+}
+
+// get last used time of path, access time is used when available otherwise
+// modification time is used. Since file system mounted with noatime never
+// update access time latest of both time is used. Listing directory content
+// (which is also done while calculating size) updates access time of
+// directory so for crate directory time of its Cargo.toml is used which is
+// read by cargo whenever crate is compiled and only modification time is used
+// for directory without Cargo.toml
+pub(crate) fn last_used(path: &Path) -> Option<SystemTime> {
+    if path.is_dir() {
+        let cargo_toml = path.join("Cargo.toml");
+        if !cargo_toml.is_file() {
+            return fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        }
+        return last_used(&cargo_toml);
+    }
+    let metadata = fs::metadata(path).ok()?;
+    match (metadata.accessed(), metadata.modified()) {
+        (Ok(accessed), Ok(modified)) => Some(accessed.max(modified)),
+        (Ok(time), Err(_)) | (Err(_), Ok(time)) => Some(time),
+        (Err(_), Err(_)) => None,
+    }
+}
+
+// convert time to seconds since unix epoch
+pub(crate) fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// get file name of path as string
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
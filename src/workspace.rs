@@ -0,0 +1,183 @@
+use crate::error::{io_error, TrimError};
+use serde_derive::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+#[derive(Default, Deserialize)]
+struct Manifest {
+    workspace: Option<WorkspaceData>,
+    package: Option<PackageData>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct WorkspaceData {
+    members: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl WorkspaceData {
+    // check if directory is excluded from workspace present in root, directory
+    // which is explicitly listed in members is never excluded. Same as cargo
+    // glob of members doesn't count as explicit member
+    fn is_excluded(&self, root: &Path, directory: &Path) -> bool {
+        let Ok(relative) = directory.strip_prefix(root) else {
+            return true;
+        };
+        let inside = |paths: &[String]| {
+            paths
+                .iter()
+                .any(|path| relative.starts_with(trim_relative(path)))
+        };
+        inside(&self.exclude) && !inside(&self.members)
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PackageData {
+    workspace: Option<String>,
+}
+
+/// Cargo workspace of project directory, all members of workspace share
+/// Cargo.lock present in root of workspace. Package which is not part of any
+/// workspace is its own workspace
+pub struct Workspace {
+    root: PathBuf,
+    members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// return root directory of workspace
+    #[must_use]
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    /// return directory containing Cargo.toml found while scanning which
+    /// belongs to workspace
+    #[must_use]
+    pub fn members(&self) -> &[PathBuf] {
+        &self.members
+    }
+
+    /// return path of Cargo.lock file of workspace
+    #[must_use]
+    pub fn lock_file(&self) -> PathBuf {
+        self.root.join("Cargo.lock")
+    }
+}
+
+// resolve workspace of all directory containing Cargo.toml, each workspace is
+// listed once even when many of its members are present. Manifest which cannot
+// be read is treated as package without workspace and its error is pushed to
+// errors
+pub(crate) fn resolve_workspaces(
+    manifest_dirs: &[PathBuf],
+    errors: &mut Vec<TrimError>,
+) -> Vec<Workspace> {
+    let mut manifests = HashMap::new();
+    let mut workspaces = Vec::<Workspace>::new();
+    for directory in manifest_dirs {
+        let root = find_root(directory, &mut manifests, errors);
+        match workspaces
+            .iter_mut()
+            .find(|workspace| workspace.root == root)
+        {
+            Some(workspace) => workspace.members.push(directory.clone()),
+            None => workspaces.push(Workspace {
+                root,
+                members: vec![directory.clone()],
+            }),
+        }
+    }
+    workspaces.sort_by(|a, b| a.root.cmp(&b.root));
+    workspaces
+}
+
+// find root of workspace in same way as cargo, root is either set explicitly
+// by package.workspace key or it is first ancestor with workspace table which
+// doesn't exclude directory
+fn find_root(
+    directory: &Path,
+    manifests: &mut HashMap<PathBuf, Option<Manifest>>,
+    errors: &mut Vec<TrimError>,
+) -> PathBuf {
+    if let Some(manifest) = read_manifest(directory, manifests, errors) {
+        if manifest.workspace.is_some() {
+            return directory.to_path_buf();
+        }
+        if let Some(workspace) = manifest.package.as_ref().and_then(|p| p.workspace.as_ref()) {
+            return normalize(&directory.join(workspace));
+        }
+    }
+    for ancestor in directory.ancestors().skip(1) {
+        let Some(manifest) = read_manifest(ancestor, manifests, errors) else {
+            continue;
+        };
+        if let Some(workspace) = &manifest.workspace {
+            if !workspace.is_excluded(ancestor, directory) {
+                return ancestor.to_path_buf();
+            }
+        }
+    }
+    directory.to_path_buf()
+}
+
+// read Cargo.toml present in directory, manifest is read only once and cached
+// in manifests
+fn read_manifest<'a>(
+    directory: &Path,
+    manifests: &'a mut HashMap<PathBuf, Option<Manifest>>,
+    errors: &mut Vec<TrimError>,
+) -> Option<&'a Manifest> {
+    manifests
+        .entry(directory.to_path_buf())
+        .or_insert_with(|| {
+            let path = directory.join("Cargo.toml");
+            if !path.is_file() {
+                return None;
+            }
+            let manifest = fs::read_to_string(&path)
+                .map_err(io_error(&path, "read manifest"))
+                .and_then(|buffer| {
+                    toml::from_str::<Manifest>(&buffer).map_err(|source| TrimError::Toml {
+                        path: path.clone(),
+                        source,
+                    })
+                });
+            match manifest {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+// remove leading ./ and trailing / from path relative to workspace root
+fn trim_relative(path: &str) -> &str {
+    path.trim_start_matches("./").trim_end_matches('/')
+}
+
+// lexically resolve . and .. component of path without accessing file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}